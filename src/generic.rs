@@ -0,0 +1,613 @@
+// MIT + Apache 2.0
+
+//! Versions of every type in this crate that take the mode as a type parameter.
+//!
+//! [`MaybeAtomic<T, M>`] holds any [`Primitive`] `T`, and [`MaybeAtomicU32`] and its siblings
+//! are aliases for it, so that code can be generic over the type of the value.
+//!
+//! The types at the crate root are aliases for these types using the default mode for their
+//! width, e.g. [`DefaultMode32`]. Naming a
+//! mode explicitly, e.g. `generic::MaybeAtomicU32<Unsync>`, lets atomic and non-atomic
+//! versions of the same type coexist in one program.
+//!
+//! Every container has a `new` that works in every mode, so it can be called when the mode is a
+//! type parameter, and the type of the value can be inferred from its argument. It creates the
+//! storage through [`Backend`], so it can't be a `const fn`. For statics, the containers also
+//! have a `new_const` for each concrete mode, which is a `const fn` except for [`Atomic`] under
+//! loom, whose atomics can't be created in a const context. Since there is one per mode, the
+//! mode has to be known where it is called. [`MaybeAtomicCell`] has no `new_const` in the
+//! `Atomic` mode, since it converts the value to its [`CellValue::Repr`], and
+//! [`MaybeAtomicArray`] has none at all, since an array can't be mapped in a const context
+//! without unsafe code.
+//!
+//! ```
+//! use maybe_atomic::{
+//!     generic::{MaybeAtomicOption, MaybeAtomicU32},
+//!     mode::{Atomic, Backend, Unsync},
+//!     MaybeAtomic, MaybeAtomicCell,
+//! };
+//! use core::num::NonZeroU32;
+//!
+//! struct Slot<M: Backend<u32>> {
+//!     len: MaybeAtomicU32<M>,
+//!     id: MaybeAtomicOption<NonZeroU32, M>,
+//! }
+//!
+//! impl<M: Backend<u32>> Slot<M> {
+//!     fn new() -> Self {
+//!         Slot {
+//!             len: MaybeAtomicU32::new(0),
+//!             id: MaybeAtomicOption::new(None),
+//!         }
+//!     }
+//! }
+//!
+//! // The type of the value is inferred, and the mode is the default one for its width.
+//! let count = MaybeAtomic::new(5u32);
+//! let ratio = MaybeAtomicCell::new(0.5f32);
+//!
+//! static ID: MaybeAtomicOption<NonZeroU32, Atomic> =
+//!     MaybeAtomicOption::<NonZeroU32, Atomic>::new_const(None);
+//! const UNSYNC_LEN: MaybeAtomicU32<Unsync> = MaybeAtomicU32::<Unsync>::new_const(0);
+//! # let _ = (Slot::<Unsync>::new(), count, ratio, &ID, UNSYNC_LEN);
+//! ```
+
+mod array;
+mod cell;
+mod niche;
+pub use array::MaybeAtomicArray;
+pub use cell::{Bits, CellValue, MaybeAtomicCell};
+pub use niche::{
+    MaybeAtomicChar, MaybeAtomicNonZeroI128, MaybeAtomicNonZeroI16, MaybeAtomicNonZeroI32,
+    MaybeAtomicNonZeroI64, MaybeAtomicNonZeroI8, MaybeAtomicNonZeroIsize, MaybeAtomicNonZeroU128,
+    MaybeAtomicNonZeroU16, MaybeAtomicNonZeroU32, MaybeAtomicNonZeroU64, MaybeAtomicNonZeroU8,
+    MaybeAtomicNonZeroUsize, MaybeAtomicOption, NonZeroInt,
+};
+
+#[cfg(feature = "critical-section")]
+use crate::mode::CriticalSection;
+use crate::mode::{
+    Atomic, AtomicBackend, Backend, BoolBackend, DefaultMode128, DefaultMode16, DefaultMode32,
+    DefaultMode64, DefaultMode8, DefaultModePtr, IntBackend, MutBackend, Primitive, PtrBackend,
+    Unsync,
+};
+use core::{cell::Cell, fmt, ptr, sync::atomic::Ordering};
+use doc_comment::doc_comment;
+
+mod sealed {
+    pub trait MaybeAtomicPrimitive {}
+}
+
+/// A container holding a [`Primitive`], so that code can be generic over its width.
+///
+/// This is implemented by [`MaybeAtomic`], and so by its aliases such as [`MaybeAtomicU32`] and
+/// [`MaybeAtomicPtr`]. The methods are the same as its inherent methods. This trait is sealed.
+///
+/// ```
+/// use core::sync::atomic::Ordering;
+/// use maybe_atomic::{generic::MaybeAtomicPrimitive, MaybeAtomicOf, MaybeAtomicU8};
+///
+/// fn reset<C: MaybeAtomicPrimitive>(counter: &C) -> C::Primitive
+/// where
+///     C::Primitive: Default,
+/// {
+///     counter.swap(Default::default(), Ordering::AcqRel)
+/// }
+///
+/// assert_eq!(reset(&MaybeAtomicU8::new(3)), 3);
+/// assert_eq!(reset(&MaybeAtomicOf::<u64>::from(5)), 5);
+/// ```
+pub trait MaybeAtomicPrimitive: Sized + sealed::MaybeAtomicPrimitive {
+    /// The type held by this container.
+    type Primitive: Primitive;
+
+    /// The mode backing this container.
+    type Mode: Backend<Self::Primitive>;
+
+    /// Creates a new container holding `val`.
+    fn new(val: Self::Primitive) -> Self;
+
+    /// Copy the value out of this container using the specified ordering.
+    fn load(&self, order: Ordering) -> Self::Primitive;
+
+    /// Store a value in this container.
+    fn store(&self, val: Self::Primitive, order: Ordering);
+
+    /// Swap two values, returning the old value stored in this container.
+    fn swap(&self, val: Self::Primitive, order: Ordering) -> Self::Primitive;
+
+    /// Get a mutable reference to the value contained within.
+    ///
+    /// See [`MutBackend`] for the modes that support this.
+    fn get_mut(&mut self) -> &mut Self::Primitive
+    where
+        Self::Mode: MutBackend<Self::Primitive>;
+}
+
+/// A [`Primitive`] that has a container in this module, which is named by `MaybeAtomic`.
+///
+/// `<u32 as HasMaybeAtomic<M>>::MaybeAtomic` is [`MaybeAtomicU32<M>`], and so on. The mode
+/// defaults to the default mode for the width of the type, so
+/// `<u32 as HasMaybeAtomic>::MaybeAtomic` is the [`MaybeAtomicU32`](crate::MaybeAtomicU32) at
+/// the crate root. [`MaybeAtomicOf`] is a shorter way to write this.
+///
+/// Like [`Primitive`], this trait is sealed.
+pub trait HasMaybeAtomic<M: Backend<Self> = <Self as Primitive>::DefaultMode>: Primitive {
+    /// The container for this type using the mode `M`.
+    type MaybeAtomic: MaybeAtomicPrimitive<Primitive = Self, Mode = M>;
+}
+
+/// The container for `T` using the mode `M`, e.g. `MaybeAtomicOf<u32, Unsync>` is
+/// `MaybeAtomicU32<Unsync>`.
+pub type MaybeAtomicOf<T, M = <T as Primitive>::DefaultMode> =
+    <T as HasMaybeAtomic<M>>::MaybeAtomic;
+
+/// An atomic structure that wraps either the atomic type for `T` or a `T`, depending on the
+/// mode `M`.
+///
+/// `T` can be `bool`, any integer, `f32`, `f64` or a raw pointer. The mode defaults to the
+/// default mode for the width of `T`, so e.g. `MaybeAtomic<u32>` is atomic exactly when
+/// [`DefaultMode32`] is [`Atomic`]. There are aliases for each `T`, e.g. [`MaybeAtomicU32`].
+///
+/// Floats are stored as their bits in an `AtomicU32` or `AtomicU64` in atomic mode, and `u128`
+/// and `i128` may be protected by a spinlock, see [`DefaultMode128`].
+#[repr(transparent)]
+pub struct MaybeAtomic<T: Primitive, M: Backend<T> = <T as Primitive>::DefaultMode> {
+    inner: M::Storage,
+}
+
+impl<T: Primitive, M: Backend<T>> MaybeAtomic<T, M> {
+    /// Creates a new instance of MaybeAtomic.
+    #[inline]
+    pub fn new(val: T) -> Self {
+        Self { inner: M::new(val) }
+    }
+
+    /// Returns `true` if operations on this type never take a lock.
+    ///
+    /// This is `false` for 128-bit types that fall back to a spinlock and for the
+    /// `CriticalSection` mode.
+    #[inline]
+    pub fn is_lock_free() -> bool {
+        M::is_lock_free()
+    }
+
+    /// Consume this container and return the value contained within.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        M::into_inner(self.inner)
+    }
+
+    /// Copy the value out of this container using the specified ordering.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> T {
+        M::load(&self.inner, order)
+    }
+
+    /// Store a value in this container.
+    #[inline]
+    pub fn store(&self, val: T, order: Ordering) {
+        M::store(&self.inner, val, order);
+    }
+
+    /// Swap two values, returning the old value stored in this container.
+    #[inline]
+    pub fn swap(&self, val: T, order: Ordering) -> T {
+        M::swap(&self.inner, val, order)
+    }
+
+    /// Store `new` in this container if the current value is equal to `current`.
+    ///
+    /// The return value is `Ok` containing the previous value if the exchange took place, and
+    /// `Err` containing the current value otherwise.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        M::compare_exchange(&self.inner, current, new, success, failure)
+    }
+
+    /// Store `new` in this container if the current value is equal to `current`.
+    ///
+    /// Unlike `compare_exchange`, this function is allowed to spuriously fail even when the
+    /// comparison succeeds, which can result in more efficient code on some platforms.
+    #[inline]
+    pub fn compare_exchange_weak(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        M::compare_exchange_weak(&self.inner, current, new, success, failure)
+    }
+
+    /// Fetch the value, apply a function to it that returns an optional new value, and store
+    /// that new value if the function returned `Some`.
+    ///
+    /// Returns `Ok` containing the previous value if the function returned `Some`, and `Err`
+    /// containing the previous value otherwise.
+    #[inline]
+    pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        M::fetch_update(&self.inner, set_order, fetch_order, f)
+    }
+
+    typed_ordering_methods! {T}
+    default_ordering_methods! {T}
+}
+
+impl<T: Primitive, M: MutBackend<T>> MaybeAtomic<T, M> {
+    /// Get a mutable reference to the value contained within.
+    ///
+    /// See [`MutBackend`] for the modes that support this.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        M::get_mut(&mut self.inner)
+    }
+}
+
+impl<T: Primitive, M: PtrBackend<T>> MaybeAtomic<T, M> {
+    /// Get a raw pointer to the value contained within.
+    ///
+    /// In atomic mode, accesses through this pointer have to be atomic if they can race with
+    /// other accesses. See [`PtrBackend`] for the modes that support this.
+    #[inline]
+    pub fn as_ptr(&self) -> *mut T {
+        M::as_ptr(&self.inner)
+    }
+}
+
+impl<T: Primitive, M: AtomicBackend<T>> MaybeAtomic<T, M> {
+    /// Get a reference to the atomic type holding the value, e.g. an `AtomicU32` for a `u32`,
+    /// so that it can be passed to APIs that take one.
+    ///
+    /// See [`AtomicBackend`] for the modes that support this.
+    #[inline]
+    pub fn as_atomic(&self) -> &M::Storage {
+        &self.inner
+    }
+}
+
+impl<T: Primitive, M: Backend<T, Storage = Cell<T>>> MaybeAtomic<T, M> {
+    /// Get a reference to the `Cell` holding the value, so that it can be passed to APIs that
+    /// take one.
+    ///
+    /// This is only available in the [`Unsync`] mode.
+    #[inline]
+    pub fn as_cell(&self) -> &Cell<T> {
+        &self.inner
+    }
+}
+
+impl<T: Primitive, M: Backend<T>> sealed::MaybeAtomicPrimitive for MaybeAtomic<T, M> {}
+
+impl<T: Primitive, M: Backend<T>> MaybeAtomicPrimitive for MaybeAtomic<T, M> {
+    type Primitive = T;
+    type Mode = M;
+
+    #[inline]
+    fn new(val: T) -> Self {
+        MaybeAtomic::new(val)
+    }
+
+    #[inline]
+    fn load(&self, order: Ordering) -> T {
+        M::load(&self.inner, order)
+    }
+
+    #[inline]
+    fn store(&self, val: T, order: Ordering) {
+        M::store(&self.inner, val, order);
+    }
+
+    #[inline]
+    fn swap(&self, val: T, order: Ordering) -> T {
+        M::swap(&self.inner, val, order)
+    }
+
+    #[inline]
+    fn get_mut(&mut self) -> &mut T
+    where
+        M: MutBackend<T>,
+    {
+        M::get_mut(&mut self.inner)
+    }
+}
+
+impl<T: Primitive, M: Backend<T>> HasMaybeAtomic<M> for T {
+    type MaybeAtomic = MaybeAtomic<T, M>;
+}
+
+impl<T: Primitive, M: Backend<T>> From<T> for MaybeAtomic<T, M> {
+    #[inline]
+    fn from(inner: T) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T: Primitive + fmt::Debug, M: Backend<T>> fmt::Debug for MaybeAtomic<T, M> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
+
+/// Generate the `new_const` functions of a [`MaybeAtomic`] holding a `$unsync`, one for each
+/// mode. Loom's atomics can't be created in a const context.
+macro_rules! maybe_atomic_new {
+    ([$($gen: ident)?] $width: literal $tyname: ident $unsync: ty, |$val: ident| $bits: expr) => {
+        #[cfg(all(
+            not(any(loom, feature = "shuttle")),
+            any(feature = "portable-atomic", target_has_atomic = $width)
+        ))]
+        impl$(<$gen>)? MaybeAtomic<$unsync, Atomic> {
+            maybe_atomic_new! {
+                @new [const] $tyname $unsync,
+                |$val| <<Atomic as Backend<$unsync>>::Storage>::new($bits)
+            }
+        }
+
+        #[cfg(all(any(loom, feature = "shuttle"), any(feature = "portable-atomic", target_has_atomic = $width)))]
+        impl$(<$gen>)? MaybeAtomic<$unsync, Atomic> {
+            maybe_atomic_new! {
+                @new [] $tyname $unsync,
+                |$val| <<Atomic as Backend<$unsync>>::Storage>::new($bits)
+            }
+        }
+
+        impl$(<$gen>)? MaybeAtomic<$unsync, Unsync> {
+            maybe_atomic_new! {@new [const] $tyname $unsync, |$val| Cell::new($val)}
+        }
+
+        #[cfg(feature = "critical-section")]
+        impl$(<$gen>)? MaybeAtomic<$unsync, CriticalSection> {
+            maybe_atomic_new! {
+                @new [const] $tyname $unsync,
+                |$val| critical_section::Mutex::new(Cell::new($val))
+            }
+        }
+    };
+    (@new [$($const: tt)?] $tyname: ident $unsync: ty, |$val: ident| $storage: expr) => {
+        doc_comment! {
+            concat!("Creates a new instance of ", stringify!($tyname), " in a const context."),
+            #[inline]
+            pub $($const)? fn new_const($val: $unsync) -> Self {
+                Self { inner: $storage }
+            }
+        }
+    };
+}
+
+macro_rules! maybe_atomic_type {
+    (int $width: literal $tyname: ident<$default: ty>: $atomic: ty | $unsync: ty) => {
+        maybe_atomic_type! {$width $tyname<$default>: $atomic | $unsync, |inner| inner}
+
+        impl<M: IntBackend<$unsync>> MaybeAtomic<$unsync, M> {
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Add to the current value, returning the previous value.
+                ///
+                /// This operation wraps around on overflow.
+                fetch_add
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Subtract from the current value, returning the previous value.
+                ///
+                /// This operation wraps around on overflow.
+                fetch_sub
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Bitwise "and" with the current value, returning the previous value.
+                fetch_and
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Bitwise "nand" with the current value, returning the previous value.
+                fetch_nand
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Bitwise "or" with the current value, returning the previous value.
+                fetch_or
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Bitwise "xor" with the current value, returning the previous value.
+                fetch_xor
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Store the maximum of the current value and `val`, returning the previous
+                /// value.
+                fetch_max
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Store the minimum of the current value and `val`, returning the previous
+                /// value.
+                fetch_min
+            }
+        }
+    };
+    (bool $width: literal $tyname: ident<$default: ty>: $atomic: ty | $unsync: ty) => {
+        maybe_atomic_type! {$width $tyname<$default>: $atomic | $unsync, |inner| inner}
+
+        impl<M: BoolBackend> MaybeAtomic<$unsync, M> {
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Logical "and" with the current value, returning the previous value.
+                fetch_and
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Logical "nand" with the current value, returning the previous value.
+                fetch_nand
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Logical "or" with the current value, returning the previous value.
+                fetch_or
+            }
+
+            maybe_atomic_type! {
+                @rmw $unsync,
+                /// Logical "xor" with the current value, returning the previous value.
+                fetch_xor
+            }
+
+            /// Logical "not" with the current value, returning the previous value.
+            #[inline]
+            pub fn fetch_not(&self, order: Ordering) -> $unsync {
+                self.fetch_xor(true, order)
+            }
+        }
+    };
+    (float $width: literal $tyname: ident<$default: ty>: $atomic: ty | $unsync: ty) => {
+        maybe_atomic_type! {$width $tyname<$default>: $atomic | $unsync, |inner| inner.to_bits()}
+
+        impl<M: Backend<$unsync>> MaybeAtomic<$unsync, M> {
+            maybe_atomic_type! {
+                @float $unsync,
+                /// Add to the current value, returning the previous value.
+                fetch_add, |old, val| old + val
+            }
+
+            maybe_atomic_type! {
+                @float $unsync,
+                /// Subtract from the current value, returning the previous value.
+                fetch_sub, |old, val| old - val
+            }
+
+            maybe_atomic_type! {
+                @float $unsync,
+                /// Store the maximum of the current value and `val`, returning the previous
+                /// value.
+                ///
+                /// As with `max`, if one of the values is NaN, the other one is stored.
+                fetch_max, |old, val| old.max(val)
+            }
+
+            maybe_atomic_type! {
+                @float $unsync,
+                /// Store the minimum of the current value and `val`, returning the previous
+                /// value.
+                ///
+                /// As with `min`, if one of the values is NaN, the other one is stored.
+                fetch_min, |old, val| old.min(val)
+            }
+        }
+    };
+    (
+        @float $unsync: ty,
+        $(#[$attr: meta])*
+        $name: ident, |$old: ident, $val: ident| $op: expr
+    ) => {
+        $(#[$attr])*
+        ///
+        /// This operation is implemented with a compare-exchange loop in atomic mode.
+        #[inline]
+        pub fn $name(&self, $val: $unsync, order: Ordering) -> $unsync {
+            match self.fetch_update(order, failure_ordering(order), |$old| Some($op)) {
+                Ok(prev) | Err(prev) => prev,
+            }
+        }
+    };
+    (@rmw $unsync: ty, $(#[$attr: meta])* $name: ident) => {
+        $(#[$attr])*
+        #[inline]
+        pub fn $name(&self, val: $unsync, order: Ordering) -> $unsync {
+            M::$name(&self.inner, val, order)
+        }
+    };
+    (
+        $width: literal $tyname: ident<$default: ty>: $atomic: ty | $unsync: ty,
+        |$val: ident| $bits: expr
+    ) => {
+        doc_comment! {
+            concat!(
+                "A [`MaybeAtomic`] that wraps either an ",
+                stringify!($atomic),
+                " or a ",
+                stringify!($unsync),
+                ", depending on the mode `M`."
+            ),
+            pub type $tyname<M = $default> = MaybeAtomic<$unsync, M>;
+        }
+
+        maybe_atomic_new! {[] $width $tyname $unsync, |$val| $bits}
+
+        impl<M: Backend<$unsync>> MaybeAtomic<$unsync, M> {
+            default_ordering_methods! {@take $unsync, Default::default()}
+        }
+
+        impl<M: Backend<$unsync>> Default for MaybeAtomic<$unsync, M> {
+            #[inline]
+            fn default() -> Self {
+                Self::from(<$unsync>::default())
+            }
+        }
+    };
+}
+
+maybe_atomic_type! {bool "8" MaybeAtomicBool<DefaultMode8>: AtomicBool | bool}
+maybe_atomic_type! {int "8" MaybeAtomicU8<DefaultMode8>: AtomicU8 | u8}
+maybe_atomic_type! {int "16" MaybeAtomicU16<DefaultMode16>: AtomicU16 | u16}
+maybe_atomic_type! {int "32" MaybeAtomicU32<DefaultMode32>: AtomicU32 | u32}
+maybe_atomic_type! {int "64" MaybeAtomicU64<DefaultMode64>: AtomicU64 | u64}
+maybe_atomic_type! {int "ptr" MaybeAtomicUsize<DefaultModePtr>: AtomicUsize | usize}
+maybe_atomic_type! {int "8" MaybeAtomicI8<DefaultMode8>: AtomicI8 | i8}
+maybe_atomic_type! {int "16" MaybeAtomicI16<DefaultMode16>: AtomicI16 | i16}
+maybe_atomic_type! {int "32" MaybeAtomicI32<DefaultMode32>: AtomicI32 | i32}
+maybe_atomic_type! {int "64" MaybeAtomicI64<DefaultMode64>: AtomicI64 | i64}
+maybe_atomic_type! {int "ptr" MaybeAtomicIsize<DefaultModePtr>: AtomicIsize | isize}
+// 128-bit types are available wherever 32-bit atomics are, see `DefaultMode128`.
+maybe_atomic_type! {int "32" MaybeAtomicU128<DefaultMode128>: AtomicU128 | u128}
+maybe_atomic_type! {int "32" MaybeAtomicI128<DefaultMode128>: AtomicI128 | i128}
+maybe_atomic_type! {float "32" MaybeAtomicF32<DefaultMode32>: AtomicU32 | f32}
+maybe_atomic_type! {float "64" MaybeAtomicF64<DefaultMode64>: AtomicU64 | f64}
+
+/// A [`MaybeAtomic`] that wraps either an `AtomicPtr<T>` or a `*mut T`, depending on the mode
+/// `M`.
+pub type MaybeAtomicPtr<T, M = DefaultModePtr> = MaybeAtomic<*mut T, M>;
+
+maybe_atomic_new! {[T] "ptr" MaybeAtomicPtr *mut T, |inner| inner}
+
+impl<T, M: Backend<*mut T>> MaybeAtomic<*mut T, M> {
+    default_ordering_methods! {@take *mut T, ptr::null_mut()}
+}
+
+impl<T, M: Backend<*mut T>> Default for MaybeAtomic<*mut T, M> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(ptr::null_mut())
+    }
+}
+
+/// The strongest ordering that is valid for the load of a read-modify-write operation using
+/// `order`.
+#[inline]
+pub(crate) fn failure_ordering(order: Ordering) -> Ordering {
+    match order {
+        Ordering::Release => Ordering::Relaxed,
+        Ordering::AcqRel => Ordering::Acquire,
+        order => order,
+    }
+}
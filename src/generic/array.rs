@@ -0,0 +1,143 @@
+// MIT + Apache 2.0
+
+//! An array of `MaybeAtomic` elements that share a mode.
+
+use super::MaybeAtomic;
+use crate::mode::{Backend, Primitive};
+use core::{array, fmt, ops::Index, slice, sync::atomic::Ordering};
+
+/// A fixed-size array of [`MaybeAtomic<T, M>`] elements.
+///
+/// The elements are accessed by indexing or through [`as_slice`](Self::as_slice) and
+/// [`iter`](Self::iter), which give references to them, so every operation on a single element
+/// is the same as on a `MaybeAtomic`. This type only adds the operations on the whole array,
+/// like `snapshot` and `fill`, which access every element separately, so they are not atomic as
+/// a whole.
+///
+/// There is no `from_mut_slice` to view a `&mut [T]` as a slice of `MaybeAtomic`s, or
+/// `get_mut_slice` for the reverse. Like the methods of the same names on the atomic types in
+/// `core`, they would reinterpret the memory of one type as the other, which needs unsafe code.
+///
+/// ```
+/// use core::sync::atomic::Ordering;
+/// use maybe_atomic::MaybeAtomicArray;
+///
+/// let counts = MaybeAtomicArray::<u32, 4>::default();
+/// counts[1].set(3);
+/// assert_eq!(counts[1].fetch_add(2, Ordering::AcqRel), 3);
+/// counts.fill(1, Ordering::Release);
+/// counts[3].set(2);
+/// assert_eq!(counts.iter().map(|count| count.get()).sum::<u32>(), 5);
+/// assert_eq!(counts.snapshot(Ordering::Acquire), [1, 1, 1, 2]);
+/// ```
+pub struct MaybeAtomicArray<
+    T: Primitive,
+    const N: usize,
+    M: Backend<T> = <T as Primitive>::DefaultMode,
+> {
+    inner: [MaybeAtomic<T, M>; N],
+}
+
+impl<T: Primitive, const N: usize, M: Backend<T>> MaybeAtomicArray<T, N, M> {
+    /// Creates a new instance of MaybeAtomicArray.
+    #[inline]
+    pub fn new(inner: [T; N]) -> Self {
+        Self {
+            inner: inner.map(MaybeAtomic::new),
+        }
+    }
+
+    /// Returns `true` if operations on the elements never take a lock.
+    #[inline]
+    pub fn is_lock_free() -> bool {
+        M::is_lock_free()
+    }
+
+    /// The number of elements in this array.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if this array has no elements.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Consume this array and return the values contained within.
+    #[inline]
+    pub fn into_inner(self) -> [T; N] {
+        self.inner.map(MaybeAtomic::into_inner)
+    }
+
+    /// Get a slice of the elements of this array.
+    #[inline]
+    pub fn as_slice(&self) -> &[MaybeAtomic<T, M>] {
+        &self.inner
+    }
+
+    /// Iterate over references to the elements of this array.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, MaybeAtomic<T, M>> {
+        self.inner.iter()
+    }
+
+    /// Copy every element out of this array using the specified ordering.
+    #[inline]
+    pub fn snapshot(&self, order: Ordering) -> [T; N] {
+        array::from_fn(|index| self.inner[index].load(order))
+    }
+
+    /// Store `val` in every element of this array.
+    #[inline]
+    pub fn fill(&self, val: T, order: Ordering) {
+        for element in &self.inner {
+            element.store(val, order);
+        }
+    }
+}
+
+impl<T: Primitive, const N: usize, M: Backend<T>> Index<usize> for MaybeAtomicArray<T, N, M> {
+    type Output = MaybeAtomic<T, M>;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.inner[index]
+    }
+}
+
+impl<'a, T: Primitive, const N: usize, M: Backend<T>> IntoIterator
+    for &'a MaybeAtomicArray<T, N, M>
+{
+    type Item = &'a MaybeAtomic<T, M>;
+    type IntoIter = slice::Iter<'a, MaybeAtomic<T, M>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Primitive + Default, const N: usize, M: Backend<T>> Default for MaybeAtomicArray<T, N, M> {
+    #[inline]
+    fn default() -> Self {
+        Self::new([T::default(); N])
+    }
+}
+
+impl<T: Primitive, const N: usize, M: Backend<T>> From<[T; N]> for MaybeAtomicArray<T, N, M> {
+    #[inline]
+    fn from(inner: [T; N]) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T: Primitive + fmt::Debug, const N: usize, M: Backend<T>> fmt::Debug
+    for MaybeAtomicArray<T, N, M>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.inner).finish()
+    }
+}
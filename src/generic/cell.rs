@@ -0,0 +1,301 @@
+// MIT + Apache 2.0
+
+//! A cell for `Copy` types, which are stored as their bits in atomic mode.
+
+#[cfg(feature = "critical-section")]
+use crate::mode::CriticalSection;
+use crate::mode::{CellBackend, DefaultMode128, Primitive, Unsync};
+use bytemuck::{checked::CheckedBitPattern, AnyBitPattern, NoUninit};
+use core::{
+    cell::Cell,
+    fmt,
+    marker::PhantomData,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    sync::atomic::Ordering,
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The type that a [`CellValue`] is stored as in atomic mode.
+///
+/// This is implemented by the unsigned integers, which are stored in the same way as the
+/// `MaybeAtomic` type of their width, and by arrays of `u32`s, which are stored behind a
+/// spinlock. This trait is sealed.
+pub trait Bits: Copy + Eq + NoUninit + AnyBitPattern + sealed::Sealed {
+    /// The default mode for cells of types stored as these bits.
+    type DefaultMode;
+}
+
+macro_rules! bits {
+    ($($unsync: ty),*) => {
+        $(
+            impl sealed::Sealed for $unsync {}
+
+            impl Bits for $unsync {
+                type DefaultMode = <$unsync as Primitive>::DefaultMode;
+            }
+        )*
+    };
+}
+
+bits! {u8, u16, u32, u64, usize, u128}
+
+impl<const N: usize> sealed::Sealed for [u32; N] {}
+
+/// Like 128-bit types, arrays are stored behind a spinlock, so they use the same mode.
+impl<const N: usize> Bits for [u32; N] {
+    type DefaultMode = DefaultMode128;
+}
+
+/// A type that can be stored in a [`MaybeAtomicCell`] in atomic mode.
+///
+/// The value is converted to its `Repr` with `bytemuck`, which checks that this is sound, so
+/// `Self` has to implement `NoUninit` and `CheckedBitPattern`, e.g. by deriving `Pod`, and
+/// have the same size as `Repr`. Integers are then stored in the same way as the `MaybeAtomic`
+/// type of their width. Types wider than 128 bits can use an array of `u32`s, which is stored
+/// behind a spinlock, like `u128` on targets without 128-bit atomics.
+///
+/// The other modes store the value itself in a `Cell`, so they don't need this trait.
+///
+/// In atomic mode, `compare_exchange` compares the bits of the values, so it only works as
+/// expected if values are equal exactly when their bits are.
+///
+/// ```
+/// use bytemuck::{Pod, Zeroable};
+/// use core::sync::atomic::Ordering;
+/// use maybe_atomic::{generic::CellValue, MaybeAtomicCell};
+///
+/// #[derive(Clone, Copy, PartialEq, Eq, Debug, Pod, Zeroable)]
+/// #[repr(C)]
+/// struct Point {
+///     x: i16,
+///     y: i16,
+/// }
+///
+/// impl CellValue for Point {
+///     type Repr = u32;
+/// }
+///
+/// #[derive(Clone, Copy, PartialEq, Eq, Debug, Pod, Zeroable)]
+/// #[repr(C)]
+/// struct Line {
+///     start: [i64; 2],
+///     end: [i64; 2],
+/// }
+///
+/// impl CellValue for Line {
+///     type Repr = [u32; 8];
+/// }
+///
+/// let cell = MaybeAtomicCell::new(Point { x: 1, y: -1 });
+/// cell.store(Point { x: -2, y: 2 }, Ordering::Release);
+/// assert_eq!(cell.load(Ordering::Acquire), Point { x: -2, y: 2 });
+///
+/// let line = Line {
+///     start: [0, 0],
+///     end: [3, 4],
+/// };
+/// let cell = MaybeAtomicCell::new(line);
+/// assert_eq!(cell.swap(Line { end: [6, 8], ..line }, Ordering::AcqRel), line);
+/// ```
+pub trait CellValue: Copy + NoUninit + CheckedBitPattern {
+    /// The type this type is stored as, which has the same size.
+    type Repr: Bits;
+}
+
+macro_rules! cell_value {
+    ($($ty: ty: $bits: ty),*) => {
+        $(
+            impl CellValue for $ty {
+                type Repr = $bits;
+            }
+        )*
+    };
+    (nonzero $($ty: ty: $bits: ty),*) => {
+        cell_value! {
+            $($ty: $bits, Option<$ty>: $bits),*
+        }
+    };
+}
+
+cell_value! {
+    u8: u8, u16: u16, u32: u32, u64: u64, usize: usize, u128: u128,
+    i8: u8, i16: u16, i32: u32, i64: u64, isize: usize, i128: u128,
+    bool: u8, char: u32, f32: u32, f64: u64
+}
+
+cell_value! {
+    nonzero
+    NonZeroU8: u8, NonZeroU16: u16, NonZeroU32: u32, NonZeroU64: u64, NonZeroUsize: usize,
+    NonZeroU128: u128, NonZeroI8: u8, NonZeroI16: u16, NonZeroI32: u32, NonZeroI64: u64,
+    NonZeroIsize: usize, NonZeroI128: u128
+}
+
+/// A cell for any `Copy` type, with the mode `M`.
+///
+/// Modelled on crossbeam's `AtomicCell`. The [`Unsync`](crate::mode::Unsync) and
+/// `CriticalSection` modes store any `T` in a `Cell`, while the
+/// [`Atomic`](crate::mode::Atomic) mode requires `T` to implement [`CellValue`] and stores
+/// it as an integer. The default mode is the default for the width of `T::Repr`, so e.g. a type
+/// stored as a `u32` is atomic exactly when `MaybeAtomicU32` is. Types that don't implement
+/// `CellValue` have no default mode, so it has to be named.
+///
+/// ```
+/// use maybe_atomic::{generic::MaybeAtomicCell, mode::Unsync};
+/// use core::sync::atomic::Ordering;
+///
+/// let cell = MaybeAtomicCell::<[u64; 4], Unsync>::from([1, 2, 3, 4]);
+/// assert_eq!(cell.swap([0; 4], Ordering::AcqRel), [1, 2, 3, 4]);
+/// ```
+#[repr(transparent)]
+pub struct MaybeAtomicCell<
+    T: Copy,
+    M: CellBackend<T> = <<T as CellValue>::Repr as Bits>::DefaultMode,
+> {
+    inner: M::Storage,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> MaybeAtomicCell<T, Unsync> {
+    /// Creates a new instance of MaybeAtomicCell in a const context.
+    #[inline]
+    pub const fn new_const(inner: T) -> Self {
+        Self {
+            inner: Cell::new(inner),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<T: Copy> MaybeAtomicCell<T, CriticalSection> {
+    /// Creates a new instance of MaybeAtomicCell in a const context.
+    #[inline]
+    pub const fn new_const(inner: T) -> Self {
+        Self {
+            inner: critical_section::Mutex::new(Cell::new(inner)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Copy, M: CellBackend<T>> MaybeAtomicCell<T, M> {
+    /// Creates a new instance of MaybeAtomicCell.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: M::new(inner),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if operations on this type never take a lock.
+    #[inline]
+    pub fn is_lock_free() -> bool {
+        M::is_lock_free()
+    }
+
+    /// Consume this container and return the value contained within.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        M::into_inner(self.inner)
+    }
+
+    /// Copy the value out of this container using the specified ordering.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> T {
+        M::load(&self.inner, order)
+    }
+
+    /// Store a value in this container.
+    #[inline]
+    pub fn store(&self, val: T, order: Ordering) {
+        M::store(&self.inner, val, order);
+    }
+
+    /// Swap two values, returning the old value stored in this container.
+    #[inline]
+    pub fn swap(&self, val: T, order: Ordering) -> T {
+        M::swap(&self.inner, val, order)
+    }
+
+    /// Fetch the value, apply a function to it that returns an optional new value, and store
+    /// that new value if the function returned `Some`.
+    ///
+    /// Returns `Ok` containing the previous value if the function returned `Some`, and `Err`
+    /// containing the previous value otherwise.
+    #[inline]
+    pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        M::fetch_update(&self.inner, set_order, fetch_order, f)
+    }
+
+    typed_ordering_methods! {@base T}
+    default_ordering_methods! {T}
+}
+
+impl<T: Copy + Eq, M: CellBackend<T>> MaybeAtomicCell<T, M> {
+    /// Store `new` in this container if the current value is equal to `current`.
+    ///
+    /// The return value is `Ok` containing the previous value if the exchange took place, and
+    /// `Err` containing the current value otherwise.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        M::compare_exchange(&self.inner, current, new, success, failure)
+    }
+
+    /// Store `new` in this container if the current value is equal to `current`.
+    ///
+    /// Unlike `compare_exchange`, this function is allowed to spuriously fail even when the
+    /// comparison succeeds, which can result in more efficient code on some platforms.
+    #[inline]
+    pub fn compare_exchange_weak(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        M::compare_exchange_weak(&self.inner, current, new, success, failure)
+    }
+
+    typed_ordering_methods! {@cas T}
+}
+
+impl<T: Copy + Default, M: CellBackend<T>> MaybeAtomicCell<T, M> {
+    default_ordering_methods! {@take T, T::default()}
+}
+
+impl<T: Copy + Default, M: CellBackend<T>> Default for MaybeAtomicCell<T, M> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(T::default())
+    }
+}
+
+impl<T: Copy, M: CellBackend<T>> From<T> for MaybeAtomicCell<T, M> {
+    #[inline]
+    fn from(inner: T) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T: Copy + fmt::Debug, M: CellBackend<T>> fmt::Debug for MaybeAtomicCell<T, M> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
@@ -0,0 +1,469 @@
+// MIT + Apache 2.0
+
+//! Types that keep a niche invariant on top of the integer types.
+
+use super::{
+    MaybeAtomicI128, MaybeAtomicI16, MaybeAtomicI32, MaybeAtomicI64, MaybeAtomicI8,
+    MaybeAtomicIsize, MaybeAtomicU128, MaybeAtomicU16, MaybeAtomicU32, MaybeAtomicU64,
+    MaybeAtomicU8, MaybeAtomicUsize,
+};
+#[cfg(feature = "critical-section")]
+use crate::mode::CriticalSection;
+use crate::mode::{
+    Atomic, Backend, DefaultMode128, DefaultMode16, DefaultMode32, DefaultMode64, DefaultMode8,
+    DefaultModePtr, Primitive, Unsync,
+};
+use core::{
+    fmt,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    sync::atomic::Ordering,
+};
+use doc_comment::doc_comment;
+
+macro_rules! maybe_atomic_niche {
+    (@new [$($const: tt)?] $mode: ident $tyname: ident: $int: ident => $inner: ty) => {
+        impl $tyname<$mode> {
+            doc_comment! {
+                concat!("Creates a new instance of ", stringify!($tyname), " in a const context."),
+                #[inline]
+                pub $($const)? fn new_const(inner: $inner) -> Self {
+                    Self {
+                        inner: $int::<$mode>::new_const(Self::to_bits(inner)),
+                    }
+                }
+            }
+        }
+    };
+    (@option_new [$($const: tt)?] $mode: ident $inner: ident: $int: ident) => {
+        impl MaybeAtomicOption<$inner, $mode> {
+            /// Creates a new instance of MaybeAtomicOption in a const context.
+            #[inline]
+            pub $($const)? fn new_const(inner: Option<$inner>) -> Self {
+                Self {
+                    inner: $int::<$mode>::new_const(match inner {
+                        Some(val) => val.get(),
+                        None => 0,
+                    })
+                    .inner,
+                }
+            }
+        }
+    };
+    (
+        $width: literal $tyname: ident<$default: ty>: $int: ident<$unsync: ty> => $inner: ty,
+        |$bits: ident| $from_bits: expr,
+        |$val: ident| $to_bits: expr
+    ) => {
+        doc_comment! {
+            concat!(
+                "A ",
+                stringify!($inner),
+                " stored in a [`",
+                stringify!($int),
+                "`].\n\nEvery operation only accepts and returns valid `",
+                stringify!($inner),
+                "` values, so the integer inside never leaves that range."
+            ),
+            #[repr(transparent)]
+            pub struct $tyname<M: Backend<$unsync> = $default> {
+                inner: $int<M>,
+            }
+        }
+
+        #[cfg(all(
+            not(any(loom, feature = "shuttle")),
+            any(feature = "portable-atomic", target_has_atomic = $width)
+        ))]
+        maybe_atomic_niche! {@new [const] Atomic $tyname: $int => $inner}
+        #[cfg(all(any(loom, feature = "shuttle"), any(feature = "portable-atomic", target_has_atomic = $width)))]
+        maybe_atomic_niche! {@new [] Atomic $tyname: $int => $inner}
+        maybe_atomic_niche! {@new [const] Unsync $tyname: $int => $inner}
+        #[cfg(feature = "critical-section")]
+        maybe_atomic_niche! {@new [const] CriticalSection $tyname: $int => $inner}
+
+        impl<M: Backend<$unsync>> $tyname<M> {
+            doc_comment! {
+                concat!("Creates a new instance of ", stringify!($tyname), "."),
+                #[inline]
+                pub fn new(inner: $inner) -> Self {
+                    Self {
+                        inner: $int::new(Self::to_bits(inner)),
+                    }
+                }
+            }
+
+            /// Returns `true` if operations on this type never take a lock.
+            #[inline]
+            pub fn is_lock_free() -> bool {
+                $int::<M>::is_lock_free()
+            }
+
+            /// Consume this container and return the value contained within.
+            #[inline]
+            pub fn into_inner(self) -> $inner {
+                Self::from_bits(self.inner.into_inner())
+            }
+
+            /// Copy the value out of this container using the specified ordering.
+            #[inline]
+            pub fn load(&self, order: Ordering) -> $inner {
+                Self::from_bits(self.inner.load(order))
+            }
+
+            /// Store a value in this container.
+            #[inline]
+            pub fn store(&self, val: $inner, order: Ordering) {
+                self.inner.store(Self::to_bits(val), order);
+            }
+
+            /// Swap two values, returning the old value stored in this container.
+            #[inline]
+            pub fn swap(&self, val: $inner, order: Ordering) -> $inner {
+                Self::from_bits(self.inner.swap(Self::to_bits(val), order))
+            }
+
+            /// Store `new` in this container if the current value is equal to `current`.
+            ///
+            /// The return value is `Ok` containing the previous value if the exchange took
+            /// place, and `Err` containing the current value otherwise.
+            #[inline]
+            pub fn compare_exchange(
+                &self,
+                current: $inner,
+                new: $inner,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$inner, $inner> {
+                self.inner
+                    .compare_exchange(Self::to_bits(current), Self::to_bits(new), success, failure)
+                    .map(Self::from_bits)
+                    .map_err(Self::from_bits)
+            }
+
+            /// Store `new` in this container if the current value is equal to `current`.
+            ///
+            /// Unlike `compare_exchange`, this function is allowed to spuriously fail even
+            /// when the comparison succeeds, which can result in more efficient code on
+            /// some platforms.
+            #[inline]
+            pub fn compare_exchange_weak(
+                &self,
+                current: $inner,
+                new: $inner,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$inner, $inner> {
+                self.inner
+                    .compare_exchange_weak(
+                        Self::to_bits(current),
+                        Self::to_bits(new),
+                        success,
+                        failure,
+                    )
+                    .map(Self::from_bits)
+                    .map_err(Self::from_bits)
+            }
+
+            /// Fetch the value, apply a function to it that returns an optional new value,
+            /// and store that new value if the function returned `Some`.
+            ///
+            /// Returns `Ok` containing the previous value if the function returned `Some`,
+            /// and `Err` containing the previous value otherwise.
+            #[inline]
+            pub fn fetch_update<F>(
+                &self,
+                set_order: Ordering,
+                fetch_order: Ordering,
+                mut f: F,
+            ) -> Result<$inner, $inner>
+            where
+                F: FnMut($inner) -> Option<$inner>,
+            {
+                self.inner
+                    .fetch_update(set_order, fetch_order, |bits| {
+                        f(Self::from_bits(bits)).map(Self::to_bits)
+                    })
+                    .map(Self::from_bits)
+                    .map_err(Self::from_bits)
+            }
+
+            typed_ordering_methods! {$inner}
+            default_ordering_methods! {$inner}
+
+            #[inline]
+            fn from_bits($bits: $unsync) -> $inner {
+                match $from_bits {
+                    Some(val) => val,
+                    None => unreachable!(concat!(
+                        stringify!($tyname),
+                        " contained an invalid value"
+                    )),
+                }
+            }
+
+            #[inline]
+            const fn to_bits($val: $inner) -> $unsync {
+                $to_bits
+            }
+        }
+
+        impl<M: Backend<$unsync>> From<$inner> for $tyname<M> {
+            #[inline]
+            fn from(inner: $inner) -> Self {
+                Self::new(inner)
+            }
+        }
+
+        impl<M: Backend<$unsync>> fmt::Debug for $tyname<M> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+            }
+        }
+    };
+    (
+        nonzero
+        $($width: literal $tyname: ident<$default: ty>: $int: ident<$unsync: ty> => $inner: ident),*
+    ) => {
+        $(
+            maybe_atomic_niche! {
+                $width $tyname<$default>: $int<$unsync> => $inner,
+                |bits| $inner::new(bits),
+                |val| val.get()
+            }
+
+            #[cfg(all(
+                not(any(loom, feature = "shuttle")),
+                any(feature = "portable-atomic", target_has_atomic = $width)
+            ))]
+            maybe_atomic_niche! {@option_new [const] Atomic $inner: $int}
+            #[cfg(all(any(loom, feature = "shuttle"), any(feature = "portable-atomic", target_has_atomic = $width)))]
+            maybe_atomic_niche! {@option_new [] Atomic $inner: $int}
+            maybe_atomic_niche! {@option_new [const] Unsync $inner: $int}
+            #[cfg(feature = "critical-section")]
+            maybe_atomic_niche! {@option_new [const] CriticalSection $inner: $int}
+
+            impl sealed::Sealed for $inner {}
+
+            impl NonZeroInt for $inner {
+                type Primitive = $unsync;
+
+                #[inline]
+                fn new(bits: $unsync) -> Option<Self> {
+                    $inner::new(bits)
+                }
+
+                #[inline]
+                fn get(self) -> $unsync {
+                    $inner::get(self)
+                }
+            }
+        )*
+    };
+}
+
+maybe_atomic_niche! {
+    nonzero
+    "8" MaybeAtomicNonZeroU8<DefaultMode8>: MaybeAtomicU8<u8> => NonZeroU8,
+    "16" MaybeAtomicNonZeroU16<DefaultMode16>: MaybeAtomicU16<u16> => NonZeroU16,
+    "32" MaybeAtomicNonZeroU32<DefaultMode32>: MaybeAtomicU32<u32> => NonZeroU32,
+    "64" MaybeAtomicNonZeroU64<DefaultMode64>: MaybeAtomicU64<u64> => NonZeroU64,
+    "ptr" MaybeAtomicNonZeroUsize<DefaultModePtr>: MaybeAtomicUsize<usize> => NonZeroUsize,
+    "32" MaybeAtomicNonZeroU128<DefaultMode128>: MaybeAtomicU128<u128> => NonZeroU128,
+    "8" MaybeAtomicNonZeroI8<DefaultMode8>: MaybeAtomicI8<i8> => NonZeroI8,
+    "16" MaybeAtomicNonZeroI16<DefaultMode16>: MaybeAtomicI16<i16> => NonZeroI16,
+    "32" MaybeAtomicNonZeroI32<DefaultMode32>: MaybeAtomicI32<i32> => NonZeroI32,
+    "64" MaybeAtomicNonZeroI64<DefaultMode64>: MaybeAtomicI64<i64> => NonZeroI64,
+    "ptr" MaybeAtomicNonZeroIsize<DefaultModePtr>: MaybeAtomicIsize<isize> => NonZeroIsize,
+    "32" MaybeAtomicNonZeroI128<DefaultMode128>: MaybeAtomicI128<i128> => NonZeroI128
+}
+
+maybe_atomic_niche! {
+    "32" MaybeAtomicChar<DefaultMode32>: MaybeAtomicU32<u32> => char,
+    |bits| char::from_u32(bits),
+    |val| val as u32
+}
+
+impl<M: Backend<u32>> MaybeAtomicChar<M> {
+    default_ordering_methods! {@take char, Default::default()}
+}
+
+impl<M: Backend<u32>> Default for MaybeAtomicChar<M> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(char::default())
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A non-zero integer type, which can be stored in a [`MaybeAtomicOption`].
+///
+/// This trait is sealed and its items are an implementation detail of this crate.
+pub trait NonZeroInt: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    type Primitive: Primitive + Default;
+
+    #[doc(hidden)]
+    fn new(bits: Self::Primitive) -> Option<Self>;
+    #[doc(hidden)]
+    fn get(self) -> Self::Primitive;
+}
+
+/// An optional non-zero integer stored in a single integer, which is zero for `None`.
+///
+/// `MaybeAtomicOption<NonZeroU32>` fits "maybe an index or id" in one word, with the same
+/// mode as a `MaybeAtomicU32`.
+#[repr(transparent)]
+pub struct MaybeAtomicOption<
+    T: NonZeroInt,
+    M: Backend<T::Primitive> = <<T as NonZeroInt>::Primitive as Primitive>::DefaultMode,
+> {
+    inner: M::Storage,
+}
+
+impl<T: NonZeroInt, M: Backend<T::Primitive>> MaybeAtomicOption<T, M> {
+    /// Creates a new instance of MaybeAtomicOption.
+    #[inline]
+    pub fn new(inner: Option<T>) -> Self {
+        Self {
+            inner: M::new(to_bits(inner)),
+        }
+    }
+
+    /// Returns `true` if operations on this type never take a lock.
+    #[inline]
+    pub fn is_lock_free() -> bool {
+        M::is_lock_free()
+    }
+
+    /// Consume this container and return the value contained within.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        T::new(M::into_inner(self.inner))
+    }
+
+    /// Copy the value out of this container using the specified ordering.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> Option<T> {
+        T::new(M::load(&self.inner, order))
+    }
+
+    /// Store a value in this container.
+    #[inline]
+    pub fn store(&self, val: Option<T>, order: Ordering) {
+        M::store(&self.inner, to_bits(val), order);
+    }
+
+    /// Swap two values, returning the old value stored in this container.
+    #[inline]
+    pub fn swap(&self, val: Option<T>, order: Ordering) -> Option<T> {
+        T::new(M::swap(&self.inner, to_bits(val), order))
+    }
+
+    /// Store `new` in this container if the current value is equal to `current`.
+    ///
+    /// The return value is `Ok` containing the previous value if the exchange took place, and
+    /// `Err` containing the current value otherwise.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: Option<T>,
+        new: Option<T>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<T>, Option<T>> {
+        M::compare_exchange(
+            &self.inner,
+            to_bits(current),
+            to_bits(new),
+            success,
+            failure,
+        )
+        .map(T::new)
+        .map_err(T::new)
+    }
+
+    /// Store `new` in this container if the current value is equal to `current`.
+    ///
+    /// Unlike `compare_exchange`, this function is allowed to spuriously fail even when the
+    /// comparison succeeds, which can result in more efficient code on some platforms.
+    #[inline]
+    pub fn compare_exchange_weak(
+        &self,
+        current: Option<T>,
+        new: Option<T>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<T>, Option<T>> {
+        M::compare_exchange_weak(
+            &self.inner,
+            to_bits(current),
+            to_bits(new),
+            success,
+            failure,
+        )
+        .map(T::new)
+        .map_err(T::new)
+    }
+
+    /// Fetch the value, apply a function to it that returns an optional new value, and store
+    /// that new value if the function returned `Some`.
+    ///
+    /// Returns `Ok` containing the previous value if the function returned `Some`, and `Err`
+    /// containing the previous value otherwise.
+    #[inline]
+    pub fn fetch_update<F>(
+        &self,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        mut f: F,
+    ) -> Result<Option<T>, Option<T>>
+    where
+        F: FnMut(Option<T>) -> Option<Option<T>>,
+    {
+        M::fetch_update(&self.inner, set_order, fetch_order, |bits| {
+            f(T::new(bits)).map(to_bits)
+        })
+        .map(T::new)
+        .map_err(T::new)
+    }
+
+    typed_ordering_methods! {Option<T>}
+    default_ordering_methods! {Option<T>}
+    default_ordering_methods! {@take Option<T>, None}
+}
+
+#[inline]
+fn to_bits<T: NonZeroInt>(val: Option<T>) -> T::Primitive {
+    match val {
+        Some(val) => val.get(),
+        None => Default::default(),
+    }
+}
+
+impl<T: NonZeroInt, M: Backend<T::Primitive>> Default for MaybeAtomicOption<T, M> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(None)
+    }
+}
+
+impl<T: NonZeroInt, M: Backend<T::Primitive>> From<Option<T>> for MaybeAtomicOption<T, M> {
+    #[inline]
+    fn from(inner: Option<T>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T: NonZeroInt + fmt::Debug, M: Backend<T::Primitive>> fmt::Debug for MaybeAtomicOption<T, M> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
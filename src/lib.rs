@@ -1,153 +1,136 @@
 // MIT + Apache 2.0
 
 //! Rust atomic primitives that can be configured to not be atomic.
+//!
+//! The types at the crate root pick their backend separately for each width: a width is atomic
+//! if its `atomic-8`, `atomic-16`, `atomic-32`, `atomic-64`, `atomic-128` or `atomic-ptr`
+//! feature is enabled and the target has atomics of that width. The `atomic` feature enables
+//! all of them. Widths that aren't atomic fall back to a `Cell`, which is only accessed inside
+//! of a critical section if the `critical-section` feature is enabled.
+//!
+//! Since `core` has no 128-bit atomics, 128-bit types are protected by a spinlock built on
+//! 32-bit atomics instead. [`MaybeAtomicU128::is_lock_free`] tells whether this is the case.
+//! If the `critical-section` feature is enabled, they use a critical section instead of the
+//! spinlock, which an interrupt handler could otherwise deadlock on.
+//!
+//! If the `portable-atomic` feature is enabled, the atomic types come from the
+//! `portable-atomic` crate instead of `core`, so a width is atomic whenever its feature is
+//! enabled. On targets without compare-and-swap, `portable-atomic` only provides it if its own
+//! `critical-section` or `unsafe-assume-single-core` feature is enabled, and fails to build
+//! with an error explaining this otherwise. When built with `--cfg loom`, the atomic types come
+//! from `loom` instead, so that code using this crate can be model checked. Loom's atomics
+//! can't hand out mutable references or pointers to their value, so `get_mut` and `as_ptr` are
+//! not available in atomic mode under loom, see [`MutBackend`](mode::MutBackend). The
+//! `shuttle` feature does the same with the atomic types from `shuttle`, which are treated
+//! like loom's, so everything said about loom in this crate applies to it too.
+//!
+//! The `force-seqcst` feature makes every atomic operation use `SeqCst`, whatever ordering it
+//! is given. This is meant for debugging: if a bug goes away with it, it is likely caused by an
+//! ordering that is too weak.
+//!
+//! `new` works in every mode but isn't a `const fn`, so statics are created with `new_const`
+//! instead, see the [`generic`] module. Since a `Cell` is not `Sync`, [`maybe_atomic_static!`]
+//! should be used to declare statics, so that they are declared as thread-locals in the
+//! `Unsync` mode.
+//!
+//! [`MaybeAtomicU32`] and the other types holding a primitive are aliases for [`MaybeAtomic<T>`],
+//! which can hold any `bool`, integer, float or raw pointer.
+//!
+//! The [`sync::atomic`] module mirrors `core::sync::atomic`, so that existing code can switch to
+//! this crate by changing its `use` lines.
+//!
+//! Since the types are only `Sync` in some configurations, generic code can use the bounds in
+//! the [`marker`] module instead of `Sync` and `Send`.
+//!
+//! The [`generic`] module contains versions of these types that take the mode as a type
+//! parameter, so that atomic and non-atomic versions of the same type can coexist in one
+//! program.
 
 #![forbid(unsafe_code)]
 #![warn(rust_2018_idioms)]
 #![no_std]
 
-#[cfg(not(feature = "atomic"))]
-use core::cell::Cell;
-#[cfg(feature = "atomic")]
-use core::sync::atomic::{
-    AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32,
-    AtomicU64, AtomicU8, AtomicUsize,
+#[macro_use]
+pub mod ordering;
+#[macro_use]
+pub mod mode;
+
+pub mod generic;
+pub mod marker;
+mod statics;
+pub mod sync;
+
+pub use statics::Static;
+#[doc(hidden)]
+pub use statics::{
+    __maybe_atomic_static_128, __maybe_atomic_static_16, __maybe_atomic_static_32,
+    __maybe_atomic_static_64, __maybe_atomic_static_8, __maybe_atomic_static_mut_ptr,
+    __maybe_atomic_static_ptr,
 };
 
-use core::sync::atomic::Ordering;
 use doc_comment::doc_comment;
 
-macro_rules! maybe_atomic_type {
-    ($tyname: ident: $atomic: ty | $unsync: ty) => {
-        doc_comment! {
-            concat!(
-                "An atomic structure that wraps either an ",
-                stringify!($atomic),
-                " or a ",
-                stringify!($unsync),
-                ", depending on if atomics are available."
-            ),
-            #[repr(transparent)]
-            pub struct $tyname {
-                #[cfg(feature = "atomic")]
-                atomic: $atomic,
-                #[cfg(not(feature = "atomic"))]
-                unsync: Cell<$unsync>,
-            }
-        }
-
-        impl $tyname {
+macro_rules! default_mode_alias {
+    ($($tyname: ident),*) => {
+        $(
             doc_comment! {
                 concat!(
-                    "Creates a new instance of ",
+                    "A [`generic::",
                     stringify!($tyname),
-                    "."
+                    "`] using the default mode for its width."
                 ),
-                #[inline]
-                pub fn new(inner: $unsync) -> Self {
-                    Self::new_impl(inner)
-                }
-            }
-
-            #[cfg(feature = "atomic")]
-            #[inline]
-            fn new_impl(inner: $unsync) -> Self {
-                Self {
-                    atomic: <$atomic>::new(inner),
-                }
-            }
-
-            #[cfg(not(feature = "atomic"))]
-            #[inline]
-            fn new_impl(inner: $unsync) -> Self {
-                Self {
-                    unsync: Cell::new(inner),
-                }
-            }
-
-            /// Get a mutable reference to the value contained within.
-            #[inline]
-            pub fn get_mut(&mut self) -> &mut $unsync {
-                self.get_mut_impl()
-            }
-
-            #[cfg(feature = "atomic")]
-            #[inline]
-            fn get_mut_impl(&mut self) -> &mut $unsync {
-                self.atomic.get_mut()
-            }
-
-            #[cfg(not(feature = "atomic"))]
-            #[inline]
-            fn get_mut_impl(&mut self) -> &mut $unsync {
-                self.unsync.get_mut()
-            }
-
-            /// Copy the value out of this container using the specified ordering.
-            #[inline]
-            pub fn load(&self, order: Ordering) -> $unsync {
-                self.load_impl(order)
-            }
-
-            #[cfg(feature = "atomic")]
-            #[inline]
-            fn load_impl(&self, order: Ordering) -> $unsync {
-                self.atomic.load(order)
-            }
-
-            #[cfg(not(feature = "atomic"))]
-            #[inline]
-            fn load_impl(&self, _order: Ordering) -> $unsync {
-                self.unsync.get()
+                pub type $tyname = generic::$tyname;
             }
+        )*
+    };
+}
 
-            /// Store a value in this container.
-            #[inline]
-            pub fn store(&self, val: $unsync, order: Ordering) {
-                self.store_impl(val, order);
-            }
+default_mode_alias! {
+    MaybeAtomicBool,
+    MaybeAtomicU8,
+    MaybeAtomicU16,
+    MaybeAtomicU32,
+    MaybeAtomicU64,
+    MaybeAtomicUsize,
+    MaybeAtomicI8,
+    MaybeAtomicI16,
+    MaybeAtomicI32,
+    MaybeAtomicI64,
+    MaybeAtomicIsize,
+    MaybeAtomicU128,
+    MaybeAtomicI128,
+    MaybeAtomicF32,
+    MaybeAtomicF64,
+    MaybeAtomicNonZeroU8,
+    MaybeAtomicNonZeroU16,
+    MaybeAtomicNonZeroU32,
+    MaybeAtomicNonZeroU64,
+    MaybeAtomicNonZeroUsize,
+    MaybeAtomicNonZeroU128,
+    MaybeAtomicNonZeroI8,
+    MaybeAtomicNonZeroI16,
+    MaybeAtomicNonZeroI32,
+    MaybeAtomicNonZeroI64,
+    MaybeAtomicNonZeroIsize,
+    MaybeAtomicNonZeroI128,
+    MaybeAtomicChar
+}
 
-            #[cfg(feature = "atomic")]
-            #[inline]
-            fn store_impl(&self, val: $unsync, order: Ordering) {
-                self.atomic.store(val, order);
-            }
+/// A [`generic::MaybeAtomic`] using the default mode for the width of `T`.
+pub type MaybeAtomic<T> = generic::MaybeAtomic<T>;
 
-            #[cfg(not(feature = "atomic"))]
-            #[inline]
-            fn store_impl(&self, val: $unsync, _order: Ordering) {
-                self.unsync.set(val);
-            }
+/// A [`generic::MaybeAtomicPtr`] using the default mode for pointers.
+pub type MaybeAtomicPtr<T> = generic::MaybeAtomicPtr<T>;
 
-            /// Swap two values, returning the old value stored in this container.
-            #[inline]
-            pub fn swap(&self, val: $unsync, order: Ordering) -> $unsync {
-                self.swap_impl(val, order)
-            }
+/// A [`generic::MaybeAtomicOption`] using the default mode for the width of `T`.
+pub type MaybeAtomicOption<T> = generic::MaybeAtomicOption<T>;
 
-            #[cfg(feature = "atomic")]
-            #[inline]
-            fn swap_impl(&self, val: $unsync, order: Ordering) -> $unsync {
-                self.atomic.swap(val, order)
-            }
+/// A [`generic::MaybeAtomicCell`] using the default mode for the width of `T`.
+pub type MaybeAtomicCell<T> = generic::MaybeAtomicCell<T>;
 
-            #[cfg(not(feature = "atomic"))]
-            #[inline]
-            fn swap_impl(&self, val: $unsync, _order: Ordering) -> $unsync {
-                self.unsync.replace(val)
-            }
-        }
-    };
-}
+/// The container for `T` using the default mode for its width, e.g. `MaybeAtomicOf<u32>` is
+/// [`MaybeAtomicU32`].
+pub type MaybeAtomicOf<T> = generic::MaybeAtomicOf<T>;
 
-maybe_atomic_type! {MaybeAtomicBool: AtomicBool | bool}
-maybe_atomic_type! {MaybeAtomicU8: AtomicU8 | u8}
-maybe_atomic_type! {MaybeAtomicU16: AtomicU16 | u16}
-maybe_atomic_type! {MaybeAtomicU32: AtomicU32 | u32}
-maybe_atomic_type! {MaybeAtomicU64: AtomicU64 | u64}
-maybe_atomic_type! {MaybeAtomicUsize: AtomicUsize | usize}
-maybe_atomic_type! {MaybeAtomicI8: AtomicI8 | i8}
-maybe_atomic_type! {MaybeAtomicI16: AtomicI16 | i16}
-maybe_atomic_type! {MaybeAtomicI32: AtomicI32 | i32}
-maybe_atomic_type! {MaybeAtomicI64: AtomicI64 | i64}
-maybe_atomic_type! {MaybeAtomicIsize: AtomicIsize | isize}
+/// A [`generic::MaybeAtomicArray`] using the default mode for the width of `T`.
+pub type MaybeAtomicArray<T, const N: usize> = generic::MaybeAtomicArray<T, N>;
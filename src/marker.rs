@@ -0,0 +1,109 @@
+// MIT + Apache 2.0
+
+//! Marker traits that only require `Send` and `Sync` when every type in this crate is atomic.
+//!
+//! The types in this crate are `Sync` in the [`Atomic`](crate::mode::Atomic) mode, but not in
+//! the [`Unsync`](crate::mode::Unsync) mode, so generic code that bounds a type parameter by
+//! `Sync` compiles in some configurations of this crate and fails in others. Bounding it by
+//! [`MaybeSync`] instead works in both: `MaybeSync` is `Sync` if the default mode for every
+//! width is `Atomic`, and is implemented by every type otherwise. [`MaybeSend`] is the same for
+//! `Send`, since `MaybeAtomicPtr` is only `Send` in the `Atomic` mode.
+//!
+//! The `CriticalSection` mode counts as not atomic here, even though most types are `Sync` in
+//! it, since `MaybeAtomicPtr` isn't.
+//!
+//! [`if_sync!`] and [`if_unsync!`] compile items only in one of the two cases, e.g. to declare
+//! a static or to add a bound that only holds when the types are `Sync`.
+//!
+//! ```
+//! use maybe_atomic::{if_sync, marker::MaybeSync, MaybeAtomicU32};
+//!
+//! struct Counter {
+//!     count: MaybeAtomicU32,
+//! }
+//!
+//! fn share<T: MaybeSync>(_: &T) {}
+//!
+//! // This compiles in every configuration of this crate.
+//! share(&Counter { count: MaybeAtomicU32::new(0) });
+//!
+//! if_sync! {
+//!     // `Counter` is only `Sync` if `MaybeAtomicU32` is atomic.
+//!     static COUNTER: Counter = Counter { count: MaybeAtomicU32::new_const(0) };
+//! }
+//! ```
+//!
+//! [`if_sync!`]: crate::if_sync
+//! [`if_unsync!`]: crate::if_unsync
+
+if_default_atomic! {
+    [8, 16, 32, 64, 128, ptr] {
+        mod imp {
+            pub use crate::{__maybe_atomic_drop as __if_unsync, __maybe_atomic_keep as __if_sync};
+            pub use core::marker::{Send as SendBound, Sync as SyncBound};
+        }
+    } else {
+        mod imp {
+            pub use crate::{__maybe_atomic_drop as __if_sync, __maybe_atomic_keep as __if_unsync};
+
+            pub trait SendBound {}
+            impl<T: ?Sized> SendBound for T {}
+
+            pub trait SyncBound {}
+            impl<T: ?Sized> SyncBound for T {}
+        }
+    }
+}
+
+#[doc(hidden)]
+pub use imp::{__if_sync, __if_unsync};
+
+/// `Sync` if the default mode for every width is `Atomic`, and implemented by every type
+/// otherwise.
+pub trait MaybeSync: imp::SyncBound {}
+
+impl<T: ?Sized + imp::SyncBound> MaybeSync for T {}
+
+/// `Send` if the default mode for every width is `Atomic`, and implemented by every type
+/// otherwise.
+pub trait MaybeSend: imp::SendBound {}
+
+impl<T: ?Sized + imp::SendBound> MaybeSend for T {}
+
+/// Compile the items inside only if [`MaybeSync`] and [`MaybeSend`] are `Sync` and `Send`,
+/// i.e. if the default mode for every width is `Atomic`.
+///
+/// [`MaybeSync`]: crate::marker::MaybeSync
+/// [`MaybeSend`]: crate::marker::MaybeSend
+#[macro_export]
+macro_rules! if_sync {
+    ($($item: item)*) => {
+        $crate::marker::__if_sync! {$($item)*}
+    };
+}
+
+/// Compile the items inside only if [`MaybeSync`] and [`MaybeSend`] are implemented by every
+/// type, i.e. if the default mode for some width isn't `Atomic`.
+///
+/// [`MaybeSync`]: crate::marker::MaybeSync
+/// [`MaybeSend`]: crate::marker::MaybeSend
+#[macro_export]
+macro_rules! if_unsync {
+    ($($item: item)*) => {
+        $crate::marker::__if_unsync! {$($item)*}
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __maybe_atomic_keep {
+    ($($item: item)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __maybe_atomic_drop {
+    ($($item: item)*) => {};
+}
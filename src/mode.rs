@@ -0,0 +1,1395 @@
+// MIT + Apache 2.0
+
+//! Type-level selection of the backend behind each `MaybeAtomic*` type.
+//!
+//! Every type in the [`generic`](crate::generic) module takes a mode parameter that defaults to
+//! the mode selected for its width, e.g. [`DefaultMode32`] for 32-bit types. A width uses
+//! [`Atomic`] if its `atomic-*` feature is enabled and either the target has atomics of that
+//! width or the `portable-atomic` feature is enabled. Otherwise, it uses `CriticalSection` if
+//! the `critical-section` feature is enabled, and [`Unsync`] if it isn't. Naming a mode
+//! explicitly picks that backend regardless of the features enabled for this crate.
+
+use crate::generic::CellValue;
+use core::{cell::Cell, mem, sync::atomic::Ordering};
+
+#[cfg(all(not(any(loom, feature = "shuttle")), not(feature = "portable-atomic")))]
+use core::sync::atomic;
+#[cfg(loom)]
+use loom::sync::atomic;
+#[cfg(all(not(any(loom, feature = "shuttle")), feature = "portable-atomic"))]
+use portable_atomic as atomic;
+#[cfg(all(feature = "shuttle", not(loom)))]
+use shuttle::sync::atomic;
+
+#[cfg(any(feature = "portable-atomic", target_has_atomic = "32"))]
+mod lock;
+
+/// Whether the given `portable-atomic` type is lock-free on this target.
+#[cfg(all(not(any(loom, feature = "shuttle")), feature = "portable-atomic"))]
+macro_rules! native_is_lock_free {
+    ($atomic: ty) => {
+        <$atomic>::is_lock_free()
+    };
+}
+
+/// The atomic types from `core`, `loom` and `shuttle` are always lock-free.
+#[cfg(not(all(not(any(loom, feature = "shuttle")), feature = "portable-atomic")))]
+macro_rules! native_is_lock_free {
+    ($atomic: ty) => {
+        true
+    };
+}
+
+/// Back a type with the corresponding `core::sync::atomic` type.
+///
+/// This mode is only available for the widths the target has atomics for. Since `core` has no
+/// 128-bit atomics, 128-bit types are protected by a spinlock instead, which must not be shared
+/// with interrupt handlers, see [`DefaultMode128`]. If the `portable-atomic`
+/// feature is enabled, the types from the `portable-atomic` crate are used instead, and this
+/// mode is available for every width, although targets without compare-and-swap need one of
+/// the features of `portable-atomic` that provide it. When built with `--cfg loom` or the
+/// `shuttle` feature, the types from `loom::sync::atomic` or `shuttle::sync::atomic` are used
+/// so that code can be model checked.
+pub enum Atomic {}
+
+/// Back a type with a `Cell`. The resulting type is not `Sync`.
+///
+/// Like the atomic types, this mode panics if an operation is given an ordering that is invalid
+/// for it, such as a `Release` load, so that such bugs are caught in non-atomic builds too.
+pub enum Unsync {}
+
+/// Back a type with a `Cell` that is only accessed inside of a critical section.
+///
+/// Unlike [`Unsync`], the resulting type is `Sync`, so it can be shared with interrupt handlers
+/// on targets without atomics. This mode requires the `critical-section` feature.
+#[cfg(feature = "critical-section")]
+pub enum CriticalSection {}
+
+/// Compile the items in the first block if the default mode for each of `$width` is
+/// [`Atomic`], and the items in the second block otherwise.
+///
+/// This is the only place that decides which widths are atomic by default. A width is atomic if
+/// its `atomic-*` feature is enabled and either the target or `portable-atomic` has atomics of
+/// that width. 128-bit types also count as atomic with only 32-bit atomics, since they fall back
+/// to a spinlock then, unless the `critical-section` feature is enabled.
+macro_rules! if_default_atomic {
+    ([$($width: tt),+] {$($atomic: item)*} else {$($other: item)*}) => {
+        if_default_atomic! {@cfg [] [$($width),+] {$($atomic)*} {$($other)*}}
+    };
+    (@cfg [$($cfg: meta),*] [8 $(, $rest: tt)*] $atomic: tt $other: tt) => {
+        if_default_atomic! {
+            @cfg [$($cfg,)* all(
+                feature = "atomic-8",
+                any(feature = "portable-atomic", target_has_atomic = "8")
+            )]
+            [$($rest),*] $atomic $other
+        }
+    };
+    (@cfg [$($cfg: meta),*] [16 $(, $rest: tt)*] $atomic: tt $other: tt) => {
+        if_default_atomic! {
+            @cfg [$($cfg,)* all(
+                feature = "atomic-16",
+                any(feature = "portable-atomic", target_has_atomic = "16")
+            )]
+            [$($rest),*] $atomic $other
+        }
+    };
+    (@cfg [$($cfg: meta),*] [32 $(, $rest: tt)*] $atomic: tt $other: tt) => {
+        if_default_atomic! {
+            @cfg [$($cfg,)* all(
+                feature = "atomic-32",
+                any(feature = "portable-atomic", target_has_atomic = "32")
+            )]
+            [$($rest),*] $atomic $other
+        }
+    };
+    (@cfg [$($cfg: meta),*] [64 $(, $rest: tt)*] $atomic: tt $other: tt) => {
+        if_default_atomic! {
+            @cfg [$($cfg,)* all(
+                feature = "atomic-64",
+                any(feature = "portable-atomic", target_has_atomic = "64")
+            )]
+            [$($rest),*] $atomic $other
+        }
+    };
+    (@cfg [$($cfg: meta),*] [128 $(, $rest: tt)*] $atomic: tt $other: tt) => {
+        if_default_atomic! {
+            @cfg [$($cfg,)* all(
+                feature = "atomic-128",
+                any(
+                    feature = "portable-atomic",
+                    all(target_has_atomic = "32", not(feature = "critical-section"))
+                )
+            )]
+            [$($rest),*] $atomic $other
+        }
+    };
+    (@cfg [$($cfg: meta),*] [ptr $(, $rest: tt)*] $atomic: tt $other: tt) => {
+        if_default_atomic! {
+            @cfg [$($cfg,)* all(
+                feature = "atomic-ptr",
+                any(feature = "portable-atomic", target_has_atomic = "ptr")
+            )]
+            [$($rest),*] $atomic $other
+        }
+    };
+    (@cfg [$($cfg: meta),*] [] $atomic: tt $other: tt) => {
+        if_default_atomic! {@emit all($($cfg),*), $atomic $other}
+    };
+    (@emit $cfg: meta, {$($atomic: item)*} {$($other: item)*}) => {
+        $(
+            #[cfg($cfg)]
+            $atomic
+        )*
+        $(
+            #[cfg(not($cfg))]
+            $other
+        )*
+    };
+}
+
+/// Select the mode for a width, which is [`Atomic`] if `if_default_atomic!` says so.
+macro_rules! default_mode {
+    ($(#[$attr: meta])* $name: ident: $width: tt) => {
+        if_default_atomic! {
+            [$width] {
+                $(#[$attr])*
+                pub type $name = Atomic;
+            } else {
+                $(#[$attr])*
+                #[cfg(feature = "critical-section")]
+                pub type $name = CriticalSection;
+
+                $(#[$attr])*
+                #[cfg(not(feature = "critical-section"))]
+                pub type $name = Unsync;
+            }
+        }
+    };
+}
+
+default_mode! {
+    /// The mode selected for 8-bit types, including `bool`.
+    DefaultMode8: 8
+}
+
+default_mode! {
+    /// The mode selected for 16-bit types.
+    DefaultMode16: 16
+}
+
+default_mode! {
+    /// The mode selected for 32-bit types.
+    DefaultMode32: 32
+}
+
+default_mode! {
+    /// The mode selected for 64-bit types.
+    DefaultMode64: 64
+}
+
+default_mode! {
+    /// The mode selected for 128-bit types.
+    ///
+    /// Unless the `portable-atomic` feature is enabled, 128-bit types are protected by a
+    /// spinlock in atomic mode, so they are available whenever the target has 32-bit atomics.
+    /// An interrupt handler that takes the spinlock while the code it interrupted holds it would
+    /// spin forever, so `CriticalSection` is selected instead of the spinlock if the
+    /// `critical-section` feature is enabled.
+    DefaultMode128: 128
+}
+
+default_mode! {
+    /// The mode selected for pointers and pointer-sized types.
+    DefaultModePtr: ptr
+}
+
+mod sealed {
+    pub trait Sealed {}
+    pub trait Primitive {}
+
+    impl Sealed for super::Atomic {}
+    impl Sealed for super::Unsync {}
+    #[cfg(feature = "critical-section")]
+    impl Sealed for super::CriticalSection {}
+}
+
+/// A type that can be stored in the containers of this crate.
+///
+/// This trait is sealed.
+pub trait Primitive: Copy + sealed::Primitive {
+    /// The mode selected for the width of this type, e.g. [`DefaultMode32`] for `u32`.
+    type DefaultMode: Backend<Self>;
+}
+
+macro_rules! primitive {
+    ($($ty: ty: $default: ty),*) => {
+        $(
+            impl sealed::Primitive for $ty {}
+
+            impl Primitive for $ty {
+                type DefaultMode = $default;
+            }
+        )*
+    };
+}
+
+primitive! {
+    bool: DefaultMode8,
+    u8: DefaultMode8,
+    u16: DefaultMode16,
+    u32: DefaultMode32,
+    u64: DefaultMode64,
+    usize: DefaultModePtr,
+    u128: DefaultMode128,
+    i8: DefaultMode8,
+    i16: DefaultMode16,
+    i32: DefaultMode32,
+    i64: DefaultMode64,
+    isize: DefaultModePtr,
+    i128: DefaultMode128,
+    f32: DefaultMode32,
+    f64: DefaultMode64
+}
+
+impl<T> sealed::Primitive for *mut T {}
+
+impl<T> Primitive for *mut T {
+    type DefaultMode = DefaultModePtr;
+}
+
+/// A mode that can back a container holding a `T`.
+///
+/// This trait is sealed and its items are an implementation detail of this crate.
+pub trait Backend<T>: sealed::Sealed {
+    #[doc(hidden)]
+    type Storage;
+
+    #[doc(hidden)]
+    fn is_lock_free() -> bool;
+
+    #[doc(hidden)]
+    fn new(inner: T) -> Self::Storage;
+
+    #[doc(hidden)]
+    fn into_inner(storage: Self::Storage) -> T;
+
+    #[doc(hidden)]
+    fn load(storage: &Self::Storage, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn store(storage: &Self::Storage, val: T, order: Ordering);
+
+    #[doc(hidden)]
+    fn swap(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn compare_exchange(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>;
+
+    #[doc(hidden)]
+    fn compare_exchange_weak(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>;
+
+    #[doc(hidden)]
+    fn fetch_update<F>(
+        storage: &Self::Storage,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>;
+}
+
+/// A mode that can back a [`MaybeAtomicCell`](crate::generic::MaybeAtomicCell) holding a `T`.
+///
+/// [`Unsync`] and `CriticalSection` store any `Copy` type in a `Cell`. [`Atomic`] can't do the
+/// same, since sharing an arbitrary `T` between threads needs an `UnsafeCell` and unsafe code,
+/// which this crate forbids. It instead converts a [`CellValue`] to its bits with `bytemuck`,
+/// and stores them in the atomic type of their width, or behind a spinlock if they are wider
+/// than 128 bits. So it only backs cells of types that implement [`CellValue`].
+///
+/// This trait is sealed and its items are an implementation detail of this crate.
+pub trait CellBackend<T>: sealed::Sealed {
+    #[doc(hidden)]
+    type Storage;
+
+    #[doc(hidden)]
+    fn is_lock_free() -> bool;
+
+    #[doc(hidden)]
+    fn new(inner: T) -> Self::Storage;
+
+    #[doc(hidden)]
+    fn into_inner(storage: Self::Storage) -> T;
+
+    #[doc(hidden)]
+    fn load(storage: &Self::Storage, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn store(storage: &Self::Storage, val: T, order: Ordering);
+
+    #[doc(hidden)]
+    fn swap(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn compare_exchange(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq;
+
+    #[doc(hidden)]
+    fn compare_exchange_weak(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq;
+
+    #[doc(hidden)]
+    fn fetch_update<F>(
+        storage: &Self::Storage,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>;
+}
+
+/// A mode that can hand out mutable references to the `T` inside of a container.
+///
+/// Every mode implements this for every type, except for [`Atomic`] with floats, since they are
+/// stored as their bits, [`Atomic`] with 128-bit types protected by a spinlock, and [`Atomic`]
+/// when built with `--cfg loom` or the `shuttle` feature, since their atomics are treated the
+/// same way.
+pub trait MutBackend<T>: Backend<T> {
+    #[doc(hidden)]
+    fn get_mut(storage: &mut Self::Storage) -> &mut T;
+}
+
+/// A mode that can hand out a raw pointer to the `T` inside of a container.
+///
+/// Every mode implements this for every type, except for [`Atomic`] with 128-bit types
+/// protected by a spinlock, and [`Atomic`] when built with `--cfg loom` or the `shuttle`
+/// feature.
+pub trait PtrBackend<T>: Backend<T> {
+    #[doc(hidden)]
+    fn as_ptr(storage: &Self::Storage) -> *mut T;
+}
+
+/// A mode that stores a `T` in an atomic type, which containers can hand out references to.
+///
+/// This is only implemented by [`Atomic`], for every type except for 128-bit types protected by
+/// a spinlock. The storage is the type from `core::sync::atomic`, or from `portable-atomic`,
+/// `loom` or `shuttle` if they are in use. Floats are stored as their bits in an `AtomicU32` or `AtomicU64`.
+pub trait AtomicBackend<T>: Backend<T> {}
+
+/// A mode that can back a container holding the integer `T`.
+pub trait IntBackend<T>: Backend<T> {
+    #[doc(hidden)]
+    fn fetch_add(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_sub(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_and(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_nand(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_or(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_xor(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_max(storage: &Self::Storage, val: T, order: Ordering) -> T;
+
+    #[doc(hidden)]
+    fn fetch_min(storage: &Self::Storage, val: T, order: Ordering) -> T;
+}
+
+/// A mode that can back a container holding a `bool`.
+pub trait BoolBackend: Backend<bool> {
+    #[doc(hidden)]
+    fn fetch_and(storage: &Self::Storage, val: bool, order: Ordering) -> bool;
+
+    #[doc(hidden)]
+    fn fetch_nand(storage: &Self::Storage, val: bool, order: Ordering) -> bool;
+
+    #[doc(hidden)]
+    fn fetch_or(storage: &Self::Storage, val: bool, order: Ordering) -> bool;
+
+    #[doc(hidden)]
+    fn fetch_xor(storage: &Self::Storage, val: bool, order: Ordering) -> bool;
+}
+
+/// Panic if `order` can't be used for a load, with the same message as `core`.
+#[inline]
+fn check_load_ordering(order: Ordering) {
+    match order {
+        Ordering::Release => panic!("there is no such thing as a release load"),
+        Ordering::AcqRel => panic!("there is no such thing as an acquire-release load"),
+        _ => {}
+    }
+}
+
+/// Panic if `order` can't be used for a store, with the same message as `core`.
+#[inline]
+fn check_store_ordering(order: Ordering) {
+    match order {
+        Ordering::Acquire => panic!("there is no such thing as an acquire store"),
+        Ordering::AcqRel => panic!("there is no such thing as an acquire-release store"),
+        _ => {}
+    }
+}
+
+/// Panic if `order` can't be used as the failure ordering of a compare-exchange, with the same
+/// message as `core`.
+#[inline]
+fn check_failure_ordering(order: Ordering) {
+    match order {
+        Ordering::Release => panic!("there is no such thing as a release failure ordering"),
+        Ordering::AcqRel => {
+            panic!("there is no such thing as an acquire-release failure ordering")
+        }
+        _ => {}
+    }
+}
+
+/// The ordering that atomic operations use when they are given `order`.
+///
+/// This is `order` itself, unless the `force-seqcst` feature is enabled, in which case it is
+/// always `SeqCst`. `order` is still passed to `check` first, so that invalid orderings panic
+/// in the same way with or without the feature.
+#[inline]
+fn atomic_ordering(order: Ordering, check: fn(Ordering)) -> Ordering {
+    if cfg!(feature = "force-seqcst") {
+        check(order);
+        Ordering::SeqCst
+    } else {
+        order
+    }
+}
+
+/// Every ordering is valid for read-modify-write operations.
+#[inline]
+fn check_rmw_ordering(_order: Ordering) {}
+
+#[inline]
+fn cell_compare_exchange<T: Copy>(
+    cell: &Cell<T>,
+    current: T,
+    new: T,
+    eq: impl FnOnce(T, T) -> bool,
+) -> Result<T, T> {
+    let old = cell.get();
+    if eq(old, current) {
+        cell.set(new);
+        Ok(old)
+    } else {
+        Err(old)
+    }
+}
+
+#[inline]
+fn cell_fetch_update<T: Copy, F>(cell: &Cell<T>, mut f: F) -> Result<T, T>
+where
+    F: FnMut(T) -> Option<T>,
+{
+    let prev = cell.get();
+    match f(prev) {
+        Some(next) => {
+            cell.set(next);
+            Ok(prev)
+        }
+        None => Err(prev),
+    }
+}
+
+#[inline]
+fn cell_rmw<T: Copy>(cell: &Cell<T>, op: impl FnOnce(T) -> T) -> T {
+    let old = cell.get();
+    cell.set(op(old));
+    old
+}
+
+macro_rules! impl_backend {
+    (
+        @atomic $width: literal [$($gen: tt)*] $unsync: ty: $atomic: ty,
+        lock_free: $lock_free: expr
+    ) => {
+        #[cfg(any(feature = "portable-atomic", target_has_atomic = $width))]
+        impl<$($gen)*> Backend<$unsync> for Atomic {
+            type Storage = $atomic;
+
+            #[inline]
+            fn is_lock_free() -> bool {
+                $lock_free
+            }
+
+            #[inline]
+            fn new(inner: $unsync) -> $atomic {
+                <$atomic>::new(inner)
+            }
+
+            #[inline]
+            fn into_inner(storage: $atomic) -> $unsync {
+                storage.into_inner()
+            }
+
+            #[inline]
+            fn load(storage: &$atomic, order: Ordering) -> $unsync {
+                storage.load(atomic_ordering(order, check_load_ordering))
+            }
+
+            #[inline]
+            fn store(storage: &$atomic, val: $unsync, order: Ordering) {
+                storage.store(val, atomic_ordering(order, check_store_ordering));
+            }
+
+            #[inline]
+            fn swap(storage: &$atomic, val: $unsync, order: Ordering) -> $unsync {
+                storage.swap(val, atomic_ordering(order, check_rmw_ordering))
+            }
+
+            #[inline]
+            fn compare_exchange(
+                storage: &$atomic,
+                current: $unsync,
+                new: $unsync,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$unsync, $unsync> {
+                storage.compare_exchange(
+                    current,
+                    new,
+                    atomic_ordering(success, check_rmw_ordering),
+                    atomic_ordering(failure, check_failure_ordering),
+                )
+            }
+
+            #[inline]
+            fn compare_exchange_weak(
+                storage: &$atomic,
+                current: $unsync,
+                new: $unsync,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$unsync, $unsync> {
+                storage.compare_exchange_weak(
+                    current,
+                    new,
+                    atomic_ordering(success, check_rmw_ordering),
+                    atomic_ordering(failure, check_failure_ordering),
+                )
+            }
+
+            #[inline]
+            fn fetch_update<F>(
+                storage: &$atomic,
+                set_order: Ordering,
+                fetch_order: Ordering,
+                f: F,
+            ) -> Result<$unsync, $unsync>
+            where
+                F: FnMut($unsync) -> Option<$unsync>,
+            {
+                storage.fetch_update(
+                    atomic_ordering(set_order, check_rmw_ordering),
+                    atomic_ordering(fetch_order, check_load_ordering),
+                    f,
+                )
+            }
+        }
+    };
+    (@atomic_mut $width: literal [$($gen: ident)?] $unsync: ty: $atomic: ty) => {
+        #[cfg(all(
+            not(any(loom, feature = "shuttle")),
+            any(feature = "portable-atomic", target_has_atomic = $width)
+        ))]
+        impl$(<$gen>)? MutBackend<$unsync> for Atomic {
+            #[inline]
+            fn get_mut(storage: &mut $atomic) -> &mut $unsync {
+                storage.get_mut()
+            }
+        }
+
+        #[cfg(all(
+            not(any(loom, feature = "shuttle")),
+            any(feature = "portable-atomic", target_has_atomic = $width)
+        ))]
+        impl$(<$gen>)? PtrBackend<$unsync> for Atomic {
+            #[inline]
+            fn as_ptr(storage: &$atomic) -> *mut $unsync {
+                storage.as_ptr()
+            }
+        }
+
+        #[cfg(any(feature = "portable-atomic", target_has_atomic = $width))]
+        impl$(<$gen>)? AtomicBackend<$unsync> for Atomic {}
+    };
+    (float $width: literal $unsync: ty: $atomic: ty) => {
+        #[cfg(any(feature = "portable-atomic", target_has_atomic = $width))]
+        impl Backend<$unsync> for Atomic {
+            type Storage = $atomic;
+
+            #[inline]
+            fn is_lock_free() -> bool {
+                native_is_lock_free!($atomic)
+            }
+
+            #[inline]
+            fn new(inner: $unsync) -> $atomic {
+                <$atomic>::new(inner.to_bits())
+            }
+
+            #[inline]
+            fn into_inner(storage: $atomic) -> $unsync {
+                <$unsync>::from_bits(storage.into_inner())
+            }
+
+            #[inline]
+            fn load(storage: &$atomic, order: Ordering) -> $unsync {
+                <$unsync>::from_bits(storage.load(atomic_ordering(order, check_load_ordering)))
+            }
+
+            #[inline]
+            fn store(storage: &$atomic, val: $unsync, order: Ordering) {
+                storage.store(
+                    val.to_bits(),
+                    atomic_ordering(order, check_store_ordering),
+                );
+            }
+
+            #[inline]
+            fn swap(storage: &$atomic, val: $unsync, order: Ordering) -> $unsync {
+                <$unsync>::from_bits(storage.swap(
+                    val.to_bits(),
+                    atomic_ordering(order, check_rmw_ordering),
+                ))
+            }
+
+            #[inline]
+            fn compare_exchange(
+                storage: &$atomic,
+                current: $unsync,
+                new: $unsync,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$unsync, $unsync> {
+                storage
+                    .compare_exchange(
+                        current.to_bits(),
+                        new.to_bits(),
+                        atomic_ordering(success, check_rmw_ordering),
+                        atomic_ordering(failure, check_failure_ordering),
+                    )
+                    .map(<$unsync>::from_bits)
+                    .map_err(<$unsync>::from_bits)
+            }
+
+            #[inline]
+            fn compare_exchange_weak(
+                storage: &$atomic,
+                current: $unsync,
+                new: $unsync,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$unsync, $unsync> {
+                storage
+                    .compare_exchange_weak(
+                        current.to_bits(),
+                        new.to_bits(),
+                        atomic_ordering(success, check_rmw_ordering),
+                        atomic_ordering(failure, check_failure_ordering),
+                    )
+                    .map(<$unsync>::from_bits)
+                    .map_err(<$unsync>::from_bits)
+            }
+
+            #[inline]
+            fn fetch_update<F>(
+                storage: &$atomic,
+                set_order: Ordering,
+                fetch_order: Ordering,
+                mut f: F,
+            ) -> Result<$unsync, $unsync>
+            where
+                F: FnMut($unsync) -> Option<$unsync>,
+            {
+                storage
+                    .fetch_update(
+                        atomic_ordering(set_order, check_rmw_ordering),
+                        atomic_ordering(fetch_order, check_load_ordering),
+                        |bits| f(<$unsync>::from_bits(bits)).map(<$unsync>::to_bits),
+                    )
+                    .map(<$unsync>::from_bits)
+                    .map_err(<$unsync>::from_bits)
+            }
+        }
+
+        #[cfg(all(
+            not(any(loom, feature = "shuttle")),
+            any(feature = "portable-atomic", target_has_atomic = $width)
+        ))]
+        impl PtrBackend<$unsync> for Atomic {
+            #[inline]
+            fn as_ptr(storage: &$atomic) -> *mut $unsync {
+                storage.as_ptr() as *mut $unsync
+            }
+        }
+
+        #[cfg(any(feature = "portable-atomic", target_has_atomic = $width))]
+        impl AtomicBackend<$unsync> for Atomic {}
+
+        impl_backend! {@unsync [] $unsync, |a, b| a.to_bits() == b.to_bits()}
+    };
+    (@unsync [$($gen: ident)?] $unsync: ty, |$a: ident, $b: ident| $eq: expr) => {
+        impl$(<$gen>)? Backend<$unsync> for Unsync {
+            type Storage = Cell<$unsync>;
+
+            #[inline]
+            fn is_lock_free() -> bool {
+                true
+            }
+
+            #[inline]
+            fn new(inner: $unsync) -> Cell<$unsync> {
+                Cell::new(inner)
+            }
+
+            #[inline]
+            fn into_inner(storage: Cell<$unsync>) -> $unsync {
+                storage.into_inner()
+            }
+
+            #[inline]
+            fn load(storage: &Cell<$unsync>, order: Ordering) -> $unsync {
+                check_load_ordering(order);
+                storage.get()
+            }
+
+            #[inline]
+            fn store(storage: &Cell<$unsync>, val: $unsync, order: Ordering) {
+                check_store_ordering(order);
+                storage.set(val);
+            }
+
+            #[inline]
+            fn swap(storage: &Cell<$unsync>, val: $unsync, _order: Ordering) -> $unsync {
+                storage.replace(val)
+            }
+
+            #[inline]
+            fn compare_exchange(
+                storage: &Cell<$unsync>,
+                current: $unsync,
+                new: $unsync,
+                _success: Ordering,
+                failure: Ordering,
+            ) -> Result<$unsync, $unsync> {
+                check_failure_ordering(failure);
+                cell_compare_exchange(storage, current, new, |$a, $b| $eq)
+            }
+
+            #[inline]
+            fn compare_exchange_weak(
+                storage: &Cell<$unsync>,
+                current: $unsync,
+                new: $unsync,
+                _success: Ordering,
+                failure: Ordering,
+            ) -> Result<$unsync, $unsync> {
+                check_failure_ordering(failure);
+                cell_compare_exchange(storage, current, new, |$a, $b| $eq)
+            }
+
+            #[inline]
+            fn fetch_update<F>(
+                storage: &Cell<$unsync>,
+                _set_order: Ordering,
+                fetch_order: Ordering,
+                f: F,
+            ) -> Result<$unsync, $unsync>
+            where
+                F: FnMut($unsync) -> Option<$unsync>,
+            {
+                check_load_ordering(fetch_order);
+                cell_fetch_update(storage, f)
+            }
+        }
+
+        impl$(<$gen>)? MutBackend<$unsync> for Unsync {
+            #[inline]
+            fn get_mut(storage: &mut Cell<$unsync>) -> &mut $unsync {
+                storage.get_mut()
+            }
+        }
+
+        impl$(<$gen>)? PtrBackend<$unsync> for Unsync {
+            #[inline]
+            fn as_ptr(storage: &Cell<$unsync>) -> *mut $unsync {
+                storage.as_ptr()
+            }
+        }
+    };
+    (
+        @rmw $width: literal $trait: path, $unsync: ty: $atomic: ty,
+        $($name: ident: |$old: ident, $val: ident| $op: expr),*
+    ) => {
+        #[cfg(any(feature = "portable-atomic", target_has_atomic = $width))]
+        impl $trait for Atomic {
+            $(
+                #[inline]
+                fn $name(storage: &$atomic, val: $unsync, order: Ordering) -> $unsync {
+                    storage.$name(val, atomic_ordering(order, check_rmw_ordering))
+                }
+            )*
+        }
+
+        impl $trait for Unsync {
+            $(
+                #[inline]
+                fn $name(storage: &Cell<$unsync>, $val: $unsync, _order: Ordering) -> $unsync {
+                    cell_rmw(storage, |$old| $op)
+                }
+            )*
+        }
+    };
+    (int $width: literal $unsync: ty: $atomic: ty) => {
+        impl_backend! {$width $unsync: $atomic}
+        impl_backend! {@int $width $unsync: $atomic}
+    };
+    (@int $width: literal $unsync: ty: $atomic: ty) => {
+        impl_backend! {
+            @rmw $width IntBackend<$unsync>, $unsync: $atomic,
+            fetch_add: |old, val| old.wrapping_add(val),
+            fetch_sub: |old, val| old.wrapping_sub(val),
+            fetch_and: |old, val| old & val,
+            fetch_nand: |old, val| !(old & val),
+            fetch_or: |old, val| old | val,
+            fetch_xor: |old, val| old ^ val,
+            fetch_max: |old, val| old.max(val),
+            fetch_min: |old, val| old.min(val)
+        }
+    };
+    (locked $width: literal $unsync: ty: $atomic: ty) => {
+        impl_backend! {@atomic $width [] $unsync: $atomic, lock_free: false}
+        impl_backend! {@unsync [] $unsync, |a, b| a == b}
+        impl_backend! {@int $width $unsync: $atomic}
+    };
+    ($width: literal <$gen: ident> $unsync: ty: $atomic: ty) => {
+        impl_backend! {
+            @atomic $width [$gen] $unsync: $atomic,
+            lock_free: native_is_lock_free!($atomic)
+        }
+        impl_backend! {@atomic_mut $width [$gen] $unsync: $atomic}
+        impl_backend! {@unsync [$gen] $unsync, |a, b| a == b}
+    };
+    ($width: literal $unsync: ty: $atomic: ty) => {
+        impl_backend! {
+            @atomic $width [] $unsync: $atomic,
+            lock_free: native_is_lock_free!($atomic)
+        }
+        impl_backend! {@atomic_mut $width [] $unsync: $atomic}
+        impl_backend! {@unsync [] $unsync, |a, b| a == b}
+    };
+}
+
+impl_backend! {"8" bool: atomic::AtomicBool}
+impl_backend! {"ptr" <T> *mut T: atomic::AtomicPtr<T>}
+impl_backend! {int "8" u8: atomic::AtomicU8}
+impl_backend! {int "16" u16: atomic::AtomicU16}
+impl_backend! {int "32" u32: atomic::AtomicU32}
+impl_backend! {int "64" u64: atomic::AtomicU64}
+impl_backend! {int "ptr" usize: atomic::AtomicUsize}
+impl_backend! {int "8" i8: atomic::AtomicI8}
+impl_backend! {int "16" i16: atomic::AtomicI16}
+impl_backend! {int "32" i32: atomic::AtomicI32}
+impl_backend! {int "64" i64: atomic::AtomicI64}
+impl_backend! {int "ptr" isize: atomic::AtomicIsize}
+#[cfg(all(feature = "portable-atomic", not(any(loom, feature = "shuttle"))))]
+impl_backend! {int "128" u128: atomic::AtomicU128}
+#[cfg(all(feature = "portable-atomic", not(any(loom, feature = "shuttle"))))]
+impl_backend! {int "128" i128: atomic::AtomicI128}
+#[cfg(not(all(feature = "portable-atomic", not(any(loom, feature = "shuttle")))))]
+impl_backend! {locked "32" u128: lock::Locked<u128, 4>}
+#[cfg(not(all(feature = "portable-atomic", not(any(loom, feature = "shuttle")))))]
+impl_backend! {locked "32" i128: lock::Locked<i128, 4>}
+// Only used for the bits of cells of types wider than 128 bits.
+impl_backend! {@atomic "32" [const N: usize] [u32; N]: lock::Locked<[u32; N], N>, lock_free: false}
+impl_backend! {float "32" f32: atomic::AtomicU32}
+impl_backend! {float "64" f64: atomic::AtomicU64}
+
+impl_backend! {
+    @rmw "8" BoolBackend, bool: atomic::AtomicBool,
+    fetch_and: |old, val| old & val,
+    fetch_nand: |old, val| !(old & val),
+    fetch_or: |old, val| old | val,
+    fetch_xor: |old, val| old ^ val
+}
+
+#[cfg(feature = "critical-section")]
+impl<T> Backend<T> for CriticalSection
+where
+    Unsync: Backend<T, Storage = Cell<T>>,
+{
+    type Storage = critical_section::Mutex<Cell<T>>;
+
+    #[inline]
+    fn is_lock_free() -> bool {
+        false
+    }
+
+    #[inline]
+    fn new(inner: T) -> Self::Storage {
+        critical_section::Mutex::new(<Unsync as Backend<T>>::new(inner))
+    }
+
+    #[inline]
+    fn into_inner(storage: Self::Storage) -> T {
+        <Unsync as Backend<T>>::into_inner(storage.into_inner())
+    }
+
+    #[inline]
+    fn load(storage: &Self::Storage, order: Ordering) -> T {
+        critical_section::with(|cs| <Unsync as Backend<T>>::load(storage.borrow(cs), order))
+    }
+
+    #[inline]
+    fn store(storage: &Self::Storage, val: T, order: Ordering) {
+        critical_section::with(|cs| <Unsync as Backend<T>>::store(storage.borrow(cs), val, order));
+    }
+
+    #[inline]
+    fn swap(storage: &Self::Storage, val: T, order: Ordering) -> T {
+        critical_section::with(|cs| <Unsync as Backend<T>>::swap(storage.borrow(cs), val, order))
+    }
+
+    #[inline]
+    fn compare_exchange(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        critical_section::with(|cs| {
+            <Unsync as Backend<T>>::compare_exchange(
+                storage.borrow(cs),
+                current,
+                new,
+                success,
+                failure,
+            )
+        })
+    }
+
+    #[inline]
+    fn compare_exchange_weak(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        critical_section::with(|cs| {
+            <Unsync as Backend<T>>::compare_exchange_weak(
+                storage.borrow(cs),
+                current,
+                new,
+                success,
+                failure,
+            )
+        })
+    }
+
+    #[inline]
+    fn fetch_update<F>(
+        storage: &Self::Storage,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        critical_section::with(|cs| {
+            <Unsync as Backend<T>>::fetch_update(storage.borrow(cs), set_order, fetch_order, f)
+        })
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<T> MutBackend<T> for CriticalSection
+where
+    Unsync: MutBackend<T> + Backend<T, Storage = Cell<T>>,
+{
+    #[inline]
+    fn get_mut(storage: &mut Self::Storage) -> &mut T {
+        Unsync::get_mut(storage.get_mut())
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<T> PtrBackend<T> for CriticalSection
+where
+    Unsync: PtrBackend<T> + Backend<T, Storage = Cell<T>>,
+{
+    #[inline]
+    fn as_ptr(storage: &Self::Storage) -> *mut T {
+        critical_section::with(|cs| Unsync::as_ptr(storage.borrow(cs)))
+    }
+}
+
+#[cfg(feature = "critical-section")]
+macro_rules! impl_critical_section_rmw {
+    ([$($gen: ident)?] $trait: path, $ty: ty: $($name: ident),*) => {
+        impl$(<$gen>)? $trait for CriticalSection
+        where
+            Unsync: $trait + Backend<$ty, Storage = Cell<$ty>>,
+        {
+            $(
+                #[inline]
+                fn $name(storage: &Self::Storage, val: $ty, order: Ordering) -> $ty {
+                    critical_section::with(|cs| {
+                        <Unsync as $trait>::$name(storage.borrow(cs), val, order)
+                    })
+                }
+            )*
+        }
+    };
+}
+
+#[cfg(feature = "critical-section")]
+impl_critical_section_rmw! {
+    [T] IntBackend<T>, T:
+    fetch_add, fetch_sub, fetch_and, fetch_nand, fetch_or, fetch_xor, fetch_max, fetch_min
+}
+
+#[cfg(feature = "critical-section")]
+impl_critical_section_rmw! {
+    [] BoolBackend, bool:
+    fetch_and, fetch_nand, fetch_or, fetch_xor
+}
+
+/// Convert a [`CellValue`] to the bits it is stored as in atomic mode.
+#[inline]
+fn into_bits<T: CellValue>(val: T) -> T::Repr {
+    const {
+        assert!(
+            mem::size_of::<T>() == mem::size_of::<T::Repr>(),
+            "a `CellValue` must have the same size as its `Repr`"
+        );
+    }
+    bytemuck::cast(val)
+}
+
+/// Convert bits returned by [`into_bits`] back to a [`CellValue`].
+#[inline]
+fn from_bits<T: CellValue>(bits: T::Repr) -> T {
+    bytemuck::checked::cast(bits)
+}
+
+impl<T: CellValue> CellBackend<T> for Atomic
+where
+    Atomic: Backend<T::Repr>,
+{
+    type Storage = <Atomic as Backend<T::Repr>>::Storage;
+
+    #[inline]
+    fn is_lock_free() -> bool {
+        <Atomic as Backend<T::Repr>>::is_lock_free()
+    }
+
+    #[inline]
+    fn new(inner: T) -> Self::Storage {
+        <Atomic as Backend<T::Repr>>::new(into_bits(inner))
+    }
+
+    #[inline]
+    fn into_inner(storage: Self::Storage) -> T {
+        from_bits(<Atomic as Backend<T::Repr>>::into_inner(storage))
+    }
+
+    #[inline]
+    fn load(storage: &Self::Storage, order: Ordering) -> T {
+        from_bits(<Atomic as Backend<T::Repr>>::load(storage, order))
+    }
+
+    #[inline]
+    fn store(storage: &Self::Storage, val: T, order: Ordering) {
+        <Atomic as Backend<T::Repr>>::store(storage, into_bits(val), order);
+    }
+
+    #[inline]
+    fn swap(storage: &Self::Storage, val: T, order: Ordering) -> T {
+        from_bits(<Atomic as Backend<T::Repr>>::swap(
+            storage,
+            into_bits(val),
+            order,
+        ))
+    }
+
+    #[inline]
+    fn compare_exchange(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq,
+    {
+        <Atomic as Backend<T::Repr>>::compare_exchange(
+            storage,
+            into_bits(current),
+            into_bits(new),
+            success,
+            failure,
+        )
+        .map(from_bits)
+        .map_err(from_bits)
+    }
+
+    #[inline]
+    fn compare_exchange_weak(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq,
+    {
+        <Atomic as Backend<T::Repr>>::compare_exchange_weak(
+            storage,
+            into_bits(current),
+            into_bits(new),
+            success,
+            failure,
+        )
+        .map(from_bits)
+        .map_err(from_bits)
+    }
+
+    #[inline]
+    fn fetch_update<F>(
+        storage: &Self::Storage,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        mut f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        <Atomic as Backend<T::Repr>>::fetch_update(storage, set_order, fetch_order, |bits| {
+            f(from_bits(bits)).map(into_bits)
+        })
+        .map(from_bits)
+        .map_err(from_bits)
+    }
+}
+
+impl<T: Copy> CellBackend<T> for Unsync {
+    type Storage = Cell<T>;
+
+    #[inline]
+    fn is_lock_free() -> bool {
+        true
+    }
+
+    #[inline]
+    fn new(inner: T) -> Cell<T> {
+        Cell::new(inner)
+    }
+
+    #[inline]
+    fn into_inner(storage: Cell<T>) -> T {
+        storage.into_inner()
+    }
+
+    #[inline]
+    fn load(storage: &Cell<T>, order: Ordering) -> T {
+        check_load_ordering(order);
+        storage.get()
+    }
+
+    #[inline]
+    fn store(storage: &Cell<T>, val: T, order: Ordering) {
+        check_store_ordering(order);
+        storage.set(val);
+    }
+
+    #[inline]
+    fn swap(storage: &Cell<T>, val: T, _order: Ordering) -> T {
+        storage.replace(val)
+    }
+
+    #[inline]
+    fn compare_exchange(
+        storage: &Cell<T>,
+        current: T,
+        new: T,
+        _success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq,
+    {
+        check_failure_ordering(failure);
+        cell_compare_exchange(storage, current, new, |a, b| a == b)
+    }
+
+    #[inline]
+    fn compare_exchange_weak(
+        storage: &Cell<T>,
+        current: T,
+        new: T,
+        _success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq,
+    {
+        check_failure_ordering(failure);
+        cell_compare_exchange(storage, current, new, |a, b| a == b)
+    }
+
+    #[inline]
+    fn fetch_update<F>(
+        storage: &Cell<T>,
+        _set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        check_load_ordering(fetch_order);
+        cell_fetch_update(storage, f)
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl<T: Copy> CellBackend<T> for CriticalSection {
+    type Storage = critical_section::Mutex<Cell<T>>;
+
+    #[inline]
+    fn is_lock_free() -> bool {
+        false
+    }
+
+    #[inline]
+    fn new(inner: T) -> Self::Storage {
+        critical_section::Mutex::new(Cell::new(inner))
+    }
+
+    #[inline]
+    fn into_inner(storage: Self::Storage) -> T {
+        storage.into_inner().into_inner()
+    }
+
+    #[inline]
+    fn load(storage: &Self::Storage, order: Ordering) -> T {
+        critical_section::with(|cs| <Unsync as CellBackend<T>>::load(storage.borrow(cs), order))
+    }
+
+    #[inline]
+    fn store(storage: &Self::Storage, val: T, order: Ordering) {
+        critical_section::with(|cs| {
+            <Unsync as CellBackend<T>>::store(storage.borrow(cs), val, order)
+        });
+    }
+
+    #[inline]
+    fn swap(storage: &Self::Storage, val: T, order: Ordering) -> T {
+        critical_section::with(|cs| {
+            <Unsync as CellBackend<T>>::swap(storage.borrow(cs), val, order)
+        })
+    }
+
+    #[inline]
+    fn compare_exchange(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq,
+    {
+        critical_section::with(|cs| {
+            <Unsync as CellBackend<T>>::compare_exchange(
+                storage.borrow(cs),
+                current,
+                new,
+                success,
+                failure,
+            )
+        })
+    }
+
+    #[inline]
+    fn compare_exchange_weak(
+        storage: &Self::Storage,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: Eq,
+    {
+        critical_section::with(|cs| {
+            <Unsync as CellBackend<T>>::compare_exchange_weak(
+                storage.borrow(cs),
+                current,
+                new,
+                success,
+                failure,
+            )
+        })
+    }
+
+    #[inline]
+    fn fetch_update<F>(
+        storage: &Self::Storage,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        critical_section::with(|cs| {
+            <Unsync as CellBackend<T>>::fetch_update(storage.borrow(cs), set_order, fetch_order, f)
+        })
+    }
+}
@@ -0,0 +1,253 @@
+// MIT + Apache 2.0
+
+//! A spinlock-protected fallback for 128-bit types on targets without 128-bit atomics, and for
+//! cells of types wider than 128 bits.
+
+use super::{
+    atomic::{fence, AtomicU32},
+    check_failure_ordering, check_load_ordering, check_store_ordering,
+};
+use bytemuck::{AnyBitPattern, NoUninit};
+use core::{array, marker::PhantomData, mem, sync::atomic::Ordering};
+
+#[cfg(not(any(loom, feature = "shuttle")))]
+use core::hint::spin_loop;
+#[cfg(loom)]
+use loom::hint::spin_loop;
+#[cfg(all(feature = "shuttle", not(loom)))]
+use shuttle::hint::spin_loop;
+
+/// A value protected by a spinlock, with the same interface as the atomic types.
+///
+/// The value is split across `N` 32-bit atomics so that it can be shared without `unsafe` code,
+/// and converted to and from them with `bytemuck`. They are only accessed while the lock is
+/// held, so `Relaxed` loads and stores are enough. Taking and releasing the lock acquires and
+/// releases, which makes every operation at least `AcqRel`. `SeqCst` operations are
+/// additionally surrounded by `SeqCst` fences.
+pub struct Locked<T, const N: usize> {
+    lock: AtomicU32,
+    parts: [AtomicU32; N],
+    _marker: PhantomData<T>,
+}
+
+#[inline]
+fn is_seq_cst(a: Ordering, b: Ordering) -> bool {
+    a == Ordering::SeqCst || b == Ordering::SeqCst
+}
+
+impl<T: NoUninit + AnyBitPattern, const N: usize> Locked<T, N> {
+    #[inline]
+    fn lock(&self, seq_cst: bool) {
+        if seq_cst {
+            fence(Ordering::SeqCst);
+        }
+
+        while self
+            .lock
+            .compare_exchange_weak(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.lock.load(Ordering::Relaxed) != 0 {
+                spin_loop();
+            }
+        }
+    }
+
+    #[inline]
+    fn unlock(&self, seq_cst: bool) {
+        self.lock.store(0, Ordering::Release);
+
+        if seq_cst {
+            fence(Ordering::SeqCst);
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.read()
+    }
+
+    #[inline]
+    fn read(&self) -> T {
+        let parts: [u32; N] = array::from_fn(|i| self.parts[i].load(Ordering::Relaxed));
+        bytemuck::cast(parts)
+    }
+
+    #[inline]
+    fn write(&self, val: T) {
+        let parts: [u32; N] = bytemuck::cast(val);
+        for (part, bits) in self.parts.iter().zip(parts) {
+            part.store(bits, Ordering::Relaxed);
+        }
+    }
+
+    /// Run `f` on the value while holding the lock, storing the value it leaves behind.
+    #[inline]
+    fn with<R>(&self, seq_cst: bool, f: impl FnOnce(&mut T) -> R) -> R {
+        self.lock(seq_cst);
+        let old = self.read();
+        let mut val = old;
+        let result = f(&mut val);
+        if !bits_eq(&val, &old) {
+            self.write(val);
+        }
+        self.unlock(seq_cst);
+        result
+    }
+
+    #[inline]
+    pub fn load(&self, order: Ordering) -> T {
+        check_load_ordering(order);
+        self.with(order == Ordering::SeqCst, |val| *val)
+    }
+
+    #[inline]
+    pub fn store(&self, new: T, order: Ordering) {
+        check_store_ordering(order);
+        self.with(order == Ordering::SeqCst, |val| *val = new);
+    }
+
+    #[inline]
+    pub fn swap(&self, new: T, order: Ordering) -> T {
+        self.with(order == Ordering::SeqCst, |val| mem::replace(val, new))
+    }
+
+    /// Like the atomic types, this compares the bits of the values.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        check_failure_ordering(failure);
+        self.with(is_seq_cst(success, failure), |val| {
+            if bits_eq(val, &current) {
+                *val = new;
+                Ok(current)
+            } else {
+                Err(*val)
+            }
+        })
+    }
+
+    #[inline]
+    pub fn compare_exchange_weak(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        self.compare_exchange(current, new, success, failure)
+    }
+
+    /// Like the atomic types, this runs `f` outside of the lock and retries if the value
+    /// changed in the meantime, so `f` may access this value itself.
+    #[inline]
+    pub fn fetch_update<F>(
+        &self,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        mut f: F,
+    ) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        let mut prev = self.load(fetch_order);
+        while let Some(next) = f(prev) {
+            match self.compare_exchange_weak(prev, next, set_order, fetch_order) {
+                Ok(prev) => return Ok(prev),
+                Err(actual) => prev = actual,
+            }
+        }
+        Err(prev)
+    }
+}
+
+#[inline]
+fn bits_eq<T: NoUninit>(a: &T, b: &T) -> bool {
+    bytemuck::bytes_of(a) == bytemuck::bytes_of(b)
+}
+
+impl<const N: usize> Locked<[u32; N], N> {
+    #[inline]
+    pub fn new(inner: [u32; N]) -> Self {
+        Self {
+            lock: AtomicU32::new(0),
+            parts: inner.map(AtomicU32::new),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The `i`th part of a 128-bit value with the native-endian `bytes`. The parts are in memory
+/// order, which is the order that `bytemuck` converts them in.
+#[cfg(not(all(feature = "portable-atomic", not(any(loom, feature = "shuttle")))))]
+#[inline]
+const fn part(bytes: &[u8; 16], i: usize) -> u32 {
+    u32::from_ne_bytes([
+        bytes[4 * i],
+        bytes[4 * i + 1],
+        bytes[4 * i + 2],
+        bytes[4 * i + 3],
+    ])
+}
+
+#[cfg(not(all(feature = "portable-atomic", not(any(loom, feature = "shuttle")))))]
+macro_rules! locked {
+    ($unsync: ty) => {
+        impl Locked<$unsync, 4> {
+            #[cfg(not(any(loom, feature = "shuttle")))]
+            locked! {@new [const] $unsync}
+            #[cfg(any(loom, feature = "shuttle"))]
+            locked! {@new [] $unsync}
+
+            locked! {
+                @rmw $unsync,
+                fetch_add: |old, val| old.wrapping_add(val),
+                fetch_sub: |old, val| old.wrapping_sub(val),
+                fetch_and: |old, val| old & val,
+                fetch_nand: |old, val| !(old & val),
+                fetch_or: |old, val| old | val,
+                fetch_xor: |old, val| old ^ val,
+                fetch_max: |old, val| old.max(val),
+                fetch_min: |old, val| old.min(val)
+            }
+        }
+    };
+    (@new [$($const: tt)?] $unsync: ty) => {
+        #[inline]
+        pub $($const)? fn new(inner: $unsync) -> Self {
+            let bytes = inner.to_ne_bytes();
+            Self {
+                lock: AtomicU32::new(0),
+                parts: [
+                    AtomicU32::new(part(&bytes, 0)),
+                    AtomicU32::new(part(&bytes, 1)),
+                    AtomicU32::new(part(&bytes, 2)),
+                    AtomicU32::new(part(&bytes, 3)),
+                ],
+                _marker: PhantomData,
+            }
+        }
+    };
+    (@rmw $unsync: ty, $($name: ident: |$old: ident, $val: ident| $op: expr),*) => {
+        $(
+            #[inline]
+            pub fn $name(&self, $val: $unsync, order: Ordering) -> $unsync {
+                self.with(order == Ordering::SeqCst, |value| {
+                    let $old = *value;
+                    *value = $op;
+                    $old
+                })
+            }
+        )*
+    };
+}
+
+#[cfg(not(all(feature = "portable-atomic", not(any(loom, feature = "shuttle")))))]
+locked! {u128}
+#[cfg(not(all(feature = "portable-atomic", not(any(loom, feature = "shuttle")))))]
+locked! {i128}
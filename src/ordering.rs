@@ -0,0 +1,251 @@
+// MIT + Apache 2.0
+
+//! Orderings that are only able to express the orderings that are valid for an operation.
+//!
+//! Every type in this crate has `_typed` versions of its `load`, `store`, `swap`,
+//! `compare_exchange`, `compare_exchange_weak` and `fetch_update` methods that take these types
+//! instead of [`Ordering`], so that e.g. a `Release` load fails to compile instead of panicking.
+
+use core::sync::atomic::Ordering;
+
+/// An ordering that is valid for a load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadOrdering {
+    /// See [`Ordering::Relaxed`].
+    Relaxed,
+    /// See [`Ordering::Acquire`].
+    Acquire,
+    /// See [`Ordering::SeqCst`].
+    SeqCst,
+}
+
+/// An ordering that is valid for a store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StoreOrdering {
+    /// See [`Ordering::Relaxed`].
+    Relaxed,
+    /// See [`Ordering::Release`].
+    Release,
+    /// See [`Ordering::SeqCst`].
+    SeqCst,
+}
+
+/// An ordering for a read-modify-write operation, for which every ordering is valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RmwOrdering {
+    /// See [`Ordering::Relaxed`].
+    Relaxed,
+    /// See [`Ordering::Release`].
+    Release,
+    /// See [`Ordering::Acquire`].
+    Acquire,
+    /// See [`Ordering::AcqRel`].
+    AcqRel,
+    /// See [`Ordering::SeqCst`].
+    SeqCst,
+}
+
+/// The success and failure orderings of a compare-exchange.
+///
+/// The failure ordering is a load, so it can't be `Release` or `AcqRel`. Converting a
+/// [`RmwOrdering`] picks the strongest failure ordering that is valid for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CasOrdering {
+    /// The ordering used if the comparison succeeds.
+    pub success: RmwOrdering,
+    /// The ordering used if the comparison fails.
+    pub failure: LoadOrdering,
+}
+
+impl CasOrdering {
+    /// Creates a new pair of orderings.
+    #[inline]
+    pub const fn new(success: RmwOrdering, failure: LoadOrdering) -> Self {
+        Self { success, failure }
+    }
+}
+
+impl From<LoadOrdering> for Ordering {
+    #[inline]
+    fn from(order: LoadOrdering) -> Self {
+        match order {
+            LoadOrdering::Relaxed => Ordering::Relaxed,
+            LoadOrdering::Acquire => Ordering::Acquire,
+            LoadOrdering::SeqCst => Ordering::SeqCst,
+        }
+    }
+}
+
+impl From<StoreOrdering> for Ordering {
+    #[inline]
+    fn from(order: StoreOrdering) -> Self {
+        match order {
+            StoreOrdering::Relaxed => Ordering::Relaxed,
+            StoreOrdering::Release => Ordering::Release,
+            StoreOrdering::SeqCst => Ordering::SeqCst,
+        }
+    }
+}
+
+impl From<RmwOrdering> for Ordering {
+    #[inline]
+    fn from(order: RmwOrdering) -> Self {
+        match order {
+            RmwOrdering::Relaxed => Ordering::Relaxed,
+            RmwOrdering::Release => Ordering::Release,
+            RmwOrdering::Acquire => Ordering::Acquire,
+            RmwOrdering::AcqRel => Ordering::AcqRel,
+            RmwOrdering::SeqCst => Ordering::SeqCst,
+        }
+    }
+}
+
+impl From<RmwOrdering> for CasOrdering {
+    #[inline]
+    fn from(success: RmwOrdering) -> Self {
+        let failure = match success {
+            RmwOrdering::Relaxed | RmwOrdering::Release => LoadOrdering::Relaxed,
+            RmwOrdering::Acquire | RmwOrdering::AcqRel => LoadOrdering::Acquire,
+            RmwOrdering::SeqCst => LoadOrdering::SeqCst,
+        };
+        Self::new(success, failure)
+    }
+}
+
+/// Generate the `_typed` methods for a type holding a `$unsync`, which forward to the methods
+/// taking an [`Ordering`].
+macro_rules! typed_ordering_methods {
+    ($unsync: ty) => {
+        typed_ordering_methods! {@base $unsync}
+        typed_ordering_methods! {@cas $unsync}
+    };
+    (@base $unsync: ty) => {
+        /// Copy the value out of this container using the specified ordering.
+        ///
+        /// This is [`load`](Self::load), but only accepts valid orderings.
+        #[inline]
+        pub fn load_typed(&self, order: $crate::ordering::LoadOrdering) -> $unsync {
+            self.load(order.into())
+        }
+
+        /// Store a value in this container.
+        ///
+        /// This is [`store`](Self::store), but only accepts valid orderings.
+        #[inline]
+        pub fn store_typed(&self, val: $unsync, order: $crate::ordering::StoreOrdering) {
+            self.store(val, order.into());
+        }
+
+        /// Swap two values, returning the old value stored in this container.
+        ///
+        /// This is [`swap`](Self::swap), but takes a [`RmwOrdering`].
+        ///
+        /// [`RmwOrdering`]: crate::ordering::RmwOrdering
+        #[inline]
+        pub fn swap_typed(&self, val: $unsync, order: $crate::ordering::RmwOrdering) -> $unsync {
+            self.swap(val, order.into())
+        }
+
+        /// Fetch the value, apply a function to it that returns an optional new value, and
+        /// store that new value if the function returned `Some`.
+        ///
+        /// This is [`fetch_update`](Self::fetch_update), but only accepts valid orderings.
+        #[inline]
+        pub fn fetch_update_typed<F>(
+            &self,
+            order: impl Into<$crate::ordering::CasOrdering>,
+            f: F,
+        ) -> Result<$unsync, $unsync>
+        where
+            F: FnMut($unsync) -> Option<$unsync>,
+        {
+            let order = order.into();
+            self.fetch_update(order.success.into(), order.failure.into(), f)
+        }
+    };
+    (@cas $unsync: ty) => {
+        /// Store `new` in this container if the current value is equal to `current`.
+        ///
+        /// This is [`compare_exchange`](Self::compare_exchange), but only accepts valid
+        /// orderings. A [`RmwOrdering`] can be passed as `order` to use the strongest failure
+        /// ordering that is valid for it.
+        ///
+        /// [`RmwOrdering`]: crate::ordering::RmwOrdering
+        #[inline]
+        pub fn compare_exchange_typed(
+            &self,
+            current: $unsync,
+            new: $unsync,
+            order: impl Into<$crate::ordering::CasOrdering>,
+        ) -> Result<$unsync, $unsync> {
+            let order = order.into();
+            self.compare_exchange(current, new, order.success.into(), order.failure.into())
+        }
+
+        /// Store `new` in this container if the current value is equal to `current`.
+        ///
+        /// This is [`compare_exchange_weak`](Self::compare_exchange_weak), but only accepts
+        /// valid orderings.
+        #[inline]
+        pub fn compare_exchange_weak_typed(
+            &self,
+            current: $unsync,
+            new: $unsync,
+            order: impl Into<$crate::ordering::CasOrdering>,
+        ) -> Result<$unsync, $unsync> {
+            let order = order.into();
+            self.compare_exchange_weak(current, new, order.success.into(), order.failure.into())
+        }
+    };
+}
+
+/// Generate the methods for a type holding a `$unsync` that use a default ordering: `Acquire`
+/// for loads, `Release` for stores and `AcqRel` for read-modify-write operations.
+macro_rules! default_ordering_methods {
+    ($unsync: ty) => {
+        /// Copy the value out of this container using `Acquire` ordering.
+        #[inline]
+        pub fn get(&self) -> $unsync {
+            self.load(core::sync::atomic::Ordering::Acquire)
+        }
+
+        /// Store a value in this container using `Release` ordering.
+        #[inline]
+        pub fn set(&self, val: $unsync) {
+            self.store(val, core::sync::atomic::Ordering::Release);
+        }
+
+        /// Store a value in this container using `AcqRel` ordering, returning the old value.
+        #[inline]
+        pub fn replace(&self, val: $unsync) -> $unsync {
+            self.swap(val, core::sync::atomic::Ordering::AcqRel)
+        }
+
+        /// Apply a function to the value and store the result, returning the old value.
+        ///
+        /// This uses `AcqRel` ordering for the store and `Acquire` ordering for the load. `f`
+        /// may be called more than once if the value is changed by another thread in the
+        /// meantime.
+        #[inline]
+        pub fn update<F>(&self, mut f: F) -> $unsync
+        where
+            F: FnMut($unsync) -> $unsync,
+        {
+            match self.fetch_update(
+                core::sync::atomic::Ordering::AcqRel,
+                core::sync::atomic::Ordering::Acquire,
+                |val| Some(f(val)),
+            ) {
+                Ok(prev) | Err(prev) => prev,
+            }
+        }
+    };
+    (@take $unsync: ty, $default: expr) => {
+        /// Take the value out of this container using `AcqRel` ordering, leaving the default
+        /// value in its place.
+        #[inline]
+        pub fn take(&self) -> $unsync {
+            self.replace($default)
+        }
+    };
+}
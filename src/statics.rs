@@ -0,0 +1,234 @@
+// MIT + Apache 2.0
+
+//! Statics that are declared correctly in every mode.
+
+/// A static declared with [`maybe_atomic_static!`] whose type can be shared between threads.
+///
+/// In the `Unsync` mode, [`maybe_atomic_static!`] declares a `std::thread::LocalKey` instead.
+/// Both are accessed through `with`, so that the same code works in every mode.
+///
+/// [`maybe_atomic_static!`]: crate::maybe_atomic_static
+#[derive(Debug)]
+pub struct Static<T>(T);
+
+impl<T> Static<T> {
+    #[doc(hidden)]
+    #[inline]
+    pub const fn new(inner: T) -> Self {
+        Self(inner)
+    }
+
+    /// Call `f` with a reference to the value in this static.
+    #[inline]
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+/// Declare statics of the types at the crate root, in a way that works in every mode.
+///
+/// A `Cell` is not `Sync`, so `static COUNTER: MaybeAtomicU32 = MaybeAtomicU32::new_const(0);` only
+/// compiles if the width of the type is atomic or uses the `CriticalSection` mode. This macro
+/// declares a plain static wrapped in a [`Static`] in those modes, and a thread-local with
+/// `std::thread_local!` in the `Unsync` mode. Either way, the value is accessed through `with`.
+/// Since raw pointers aren't `Send`, `MaybeAtomicPtr` is only `Sync` in the `Atomic` mode, so it
+/// uses a thread-local in the `CriticalSection` mode too.
+///
+/// The type has to be named the same way as at the crate root, e.g. `MaybeAtomicU32`,
+/// `MaybeAtomic<u32>` or `MaybeAtomicPtr<T>`, since the macro uses the name to find the width.
+/// In the `Unsync` mode, the expansion requires `std`. Statics can't be declared this way when
+/// built with `--cfg loom` or the `shuttle` feature, since their atomics can't be created in a
+/// const context.
+///
+/// ```
+/// use core::sync::atomic::Ordering;
+/// use maybe_atomic::{maybe_atomic_static, MaybeAtomic, MaybeAtomicBool, MaybeAtomicU32};
+///
+/// maybe_atomic_static! {
+///     static COUNTER: MaybeAtomicU32 = MaybeAtomicU32::new_const(0);
+///     pub static READY: MaybeAtomicBool = MaybeAtomicBool::new_const(false);
+///     static LIMIT: MaybeAtomic<u16> = MaybeAtomic::<u16>::new_const(8);
+/// }
+///
+/// COUNTER.with(|counter| counter.fetch_add(1, Ordering::Relaxed));
+/// READY.with(|ready| ready.store(true, Ordering::Release));
+/// assert_eq!(COUNTER.with(|counter| counter.load(Ordering::Relaxed)), 1);
+/// assert_eq!(LIMIT.with(|limit| limit.load(Ordering::Relaxed)), 8);
+/// ```
+#[macro_export]
+macro_rules! maybe_atomic_static {
+    () => {};
+    (
+        $(#[$attr: meta])*
+        $vis: vis static $name: ident: MaybeAtomicPtr<$t: ty> = $init: expr
+        $(; $($rest: tt)*)?
+    ) => {
+        $crate::__maybe_atomic_static_mut_ptr! {
+            $(#[$attr])* $vis $name: $crate::MaybeAtomicPtr<$t> = $init
+        }
+        $crate::maybe_atomic_static! {$($($rest)*)?}
+    };
+    (
+        $(#[$attr: meta])*
+        $vis: vis static $name: ident: MaybeAtomic<*mut $t: ty> = $init: expr
+        $(; $($rest: tt)*)?
+    ) => {
+        $crate::__maybe_atomic_static_mut_ptr! {
+            $(#[$attr])* $vis $name: $crate::MaybeAtomic<*mut $t> = $init
+        }
+        $crate::maybe_atomic_static! {$($($rest)*)?}
+    };
+    (
+        $(#[$attr: meta])*
+        $vis: vis static $name: ident: MaybeAtomic<$t: ident> = $init: expr
+        $(; $($rest: tt)*)?
+    ) => {
+        $crate::__maybe_atomic_static_width! {
+            $t, $(#[$attr])* $vis $name: $crate::MaybeAtomic<$t> = $init
+        }
+        $crate::maybe_atomic_static! {$($($rest)*)?}
+    };
+    (
+        $(#[$attr: meta])*
+        $vis: vis static $name: ident: $ty: ident = $init: expr
+        $(; $($rest: tt)*)?
+    ) => {
+        $crate::__maybe_atomic_static_width! {
+            $ty, $(#[$attr])* $vis $name: $crate::$ty = $init
+        }
+        $crate::maybe_atomic_static! {$($($rest)*)?}
+    };
+}
+
+/// Forward a declaration to the helper for the width of `$ty`, which is either the name of a
+/// type at the crate root or the primitive held by a `MaybeAtomic`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __maybe_atomic_static_width {
+    (bool, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (u8, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (i8, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (u16, $($decl: tt)*) => { $crate::__maybe_atomic_static_16! {$($decl)*} };
+    (i16, $($decl: tt)*) => { $crate::__maybe_atomic_static_16! {$($decl)*} };
+    (u32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (i32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (f32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (u64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (i64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (f64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (u128, $($decl: tt)*) => { $crate::__maybe_atomic_static_128! {$($decl)*} };
+    (i128, $($decl: tt)*) => { $crate::__maybe_atomic_static_128! {$($decl)*} };
+    (usize, $($decl: tt)*) => { $crate::__maybe_atomic_static_ptr! {$($decl)*} };
+    (isize, $($decl: tt)*) => { $crate::__maybe_atomic_static_ptr! {$($decl)*} };
+    (MaybeAtomicBool, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (MaybeAtomicU8, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (MaybeAtomicI8, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (MaybeAtomicNonZeroU8, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (MaybeAtomicNonZeroI8, $($decl: tt)*) => { $crate::__maybe_atomic_static_8! {$($decl)*} };
+    (MaybeAtomicU16, $($decl: tt)*) => { $crate::__maybe_atomic_static_16! {$($decl)*} };
+    (MaybeAtomicI16, $($decl: tt)*) => { $crate::__maybe_atomic_static_16! {$($decl)*} };
+    (MaybeAtomicNonZeroU16, $($decl: tt)*) => { $crate::__maybe_atomic_static_16! {$($decl)*} };
+    (MaybeAtomicNonZeroI16, $($decl: tt)*) => { $crate::__maybe_atomic_static_16! {$($decl)*} };
+    (MaybeAtomicU32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (MaybeAtomicI32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (MaybeAtomicF32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (MaybeAtomicChar, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (MaybeAtomicNonZeroU32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (MaybeAtomicNonZeroI32, $($decl: tt)*) => { $crate::__maybe_atomic_static_32! {$($decl)*} };
+    (MaybeAtomicU64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (MaybeAtomicI64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (MaybeAtomicF64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (MaybeAtomicNonZeroU64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (MaybeAtomicNonZeroI64, $($decl: tt)*) => { $crate::__maybe_atomic_static_64! {$($decl)*} };
+    (MaybeAtomicU128, $($decl: tt)*) => { $crate::__maybe_atomic_static_128! {$($decl)*} };
+    (MaybeAtomicI128, $($decl: tt)*) => { $crate::__maybe_atomic_static_128! {$($decl)*} };
+    (MaybeAtomicNonZeroU128, $($decl: tt)*) => { $crate::__maybe_atomic_static_128! {$($decl)*} };
+    (MaybeAtomicNonZeroI128, $($decl: tt)*) => { $crate::__maybe_atomic_static_128! {$($decl)*} };
+    (MaybeAtomicUsize, $($decl: tt)*) => { $crate::__maybe_atomic_static_ptr! {$($decl)*} };
+    (MaybeAtomicIsize, $($decl: tt)*) => { $crate::__maybe_atomic_static_ptr! {$($decl)*} };
+    (MaybeAtomicNonZeroUsize, $($decl: tt)*) => { $crate::__maybe_atomic_static_ptr! {$($decl)*} };
+    (MaybeAtomicNonZeroIsize, $($decl: tt)*) => { $crate::__maybe_atomic_static_ptr! {$($decl)*} };
+    ($ty: ident, $($decl: tt)*) => {
+        compile_error!(concat!(
+            "`maybe_atomic_static!` does not support `",
+            stringify!($ty),
+            "`"
+        ));
+    };
+}
+
+/// Declare a static for a type whose mode is `Sync`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __maybe_atomic_static_sync {
+    ($(#[$attr: meta])* $vis: vis $name: ident: $ty: ty = $init: expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::Static<$ty> = $crate::Static::new($init);
+    };
+}
+
+/// Declare a thread-local for a type in the `Unsync` mode.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __maybe_atomic_static_unsync {
+    ($(#[$attr: meta])* $vis: vis $name: ident: $ty: ty = $init: expr) => {
+        ::std::thread_local! {
+            $(#[$attr])*
+            $vis static $name: $ty = $init;
+        }
+    };
+}
+
+/// Reject a static for a type in the `Atomic` mode under loom or shuttle.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __maybe_atomic_static_loom {
+    ($($decl: tt)*) => {
+        compile_error!(
+            "`maybe_atomic_static!` is not supported when built with `--cfg loom` or `shuttle`"
+        );
+    };
+}
+
+/// Select the helper for a width, which declares a plain static if the width is atomic by
+/// default, see `mode::if_default_atomic!`. `$critical_section` is the helper used in the
+/// `CriticalSection` mode.
+macro_rules! static_helper {
+    ($(#[$attr: meta])* $name: ident: $width: tt, $critical_section: ident) => {
+        if_default_atomic! {
+            [$width] {
+                $(#[$attr])*
+                #[cfg(not(any(loom, feature = "shuttle")))]
+                pub use crate::__maybe_atomic_static_sync as $name;
+
+                $(#[$attr])*
+                #[cfg(any(loom, feature = "shuttle"))]
+                pub use crate::__maybe_atomic_static_loom as $name;
+            } else {
+                $(#[$attr])*
+                #[cfg(feature = "critical-section")]
+                pub use crate::$critical_section as $name;
+
+                $(#[$attr])*
+                #[cfg(not(feature = "critical-section"))]
+                pub use crate::__maybe_atomic_static_unsync as $name;
+            }
+        }
+    };
+}
+
+static_helper! {__maybe_atomic_static_8: 8, __maybe_atomic_static_sync}
+static_helper! {__maybe_atomic_static_16: 16, __maybe_atomic_static_sync}
+static_helper! {__maybe_atomic_static_32: 32, __maybe_atomic_static_sync}
+static_helper! {__maybe_atomic_static_64: 64, __maybe_atomic_static_sync}
+static_helper! {__maybe_atomic_static_128: 128, __maybe_atomic_static_sync}
+static_helper! {__maybe_atomic_static_ptr: ptr, __maybe_atomic_static_sync}
+
+static_helper! {
+    /// Raw pointers aren't `Send`, so `MaybeAtomicPtr` isn't `Sync` in the `CriticalSection`
+    /// mode.
+    __maybe_atomic_static_mut_ptr: ptr, __maybe_atomic_static_unsync
+}
@@ -0,0 +1,5 @@
+// MIT + Apache 2.0
+
+//! Drop-in replacements for the synchronization primitives in `core::sync`.
+
+pub mod atomic;
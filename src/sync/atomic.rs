@@ -0,0 +1,445 @@
+// MIT + Apache 2.0
+
+//! A drop-in replacement for `core::sync::atomic`, backed by the types in this crate.
+//!
+//! Code using `core::sync::atomic` can be migrated by changing its `use` line, e.g. from
+//! `use core::sync::atomic::{AtomicU32, Ordering};` to
+//! `use maybe_atomic::sync::atomic::{AtomicU32, Ordering};`. Each type uses the default mode
+//! for its width, so it is only atomic if the features of this crate make it so. Like the other
+//! types in this crate, the types are not `Sync` in the `Unsync` mode.
+//!
+//! The types have the safe methods of their counterparts in `core`, but not `from_mut`,
+//! `from_ptr`, `from_mut_slice` or `get_mut_slice`. These reinterpret a `T` as the atomic type
+//! or the other way around, which needs unsafe code, and wouldn't be sound in the modes that
+//! store the value in some other way. `get_mut` and `as_ptr` are only available in the modes
+//! that support them, see [`MutBackend`] and [`PtrBackend`], so they are missing in atomic mode
+//! under loom.
+//!
+//! `fence` comes from the same crate as the atomic types, i.e. `core`, `portable-atomic`,
+//! `loom` or `shuttle`. So does `compiler_fence`, except under loom, which doesn't have one, and
+//! shuttle, so it comes from `core` instead.
+//!
+//! ```
+//! use maybe_atomic::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! let counter = AtomicUsize::new(0);
+//! counter.fetch_add(1, Ordering::Relaxed);
+//! assert_eq!(counter.into_inner(), 1);
+//! ```
+
+use crate::{
+    generic::failure_ordering,
+    mode::{MutBackend, Primitive, PtrBackend},
+    MaybeAtomic,
+};
+use core::{fmt, ptr};
+use doc_comment::doc_comment;
+
+#[cfg(any(loom, feature = "shuttle"))]
+pub use core::sync::atomic::compiler_fence;
+pub use core::sync::atomic::Ordering;
+#[cfg(all(not(any(loom, feature = "shuttle")), not(feature = "portable-atomic")))]
+pub use core::sync::atomic::{compiler_fence, fence};
+#[cfg(loom)]
+pub use loom::sync::atomic::fence;
+#[cfg(all(not(any(loom, feature = "shuttle")), feature = "portable-atomic"))]
+pub use portable_atomic::{compiler_fence, fence};
+#[cfg(all(feature = "shuttle", not(loom)))]
+pub use shuttle::sync::atomic::fence;
+
+/// A `T` that is stored in the same way as a [`MaybeAtomic<T>`], with the API of the atomic types
+/// in `core`.
+///
+/// This mirrors the unstable `core::sync::atomic::Atomic<T>`. Code migrated from `core` will
+/// usually name the aliases for it, such as [`AtomicU32`].
+#[repr(transparent)]
+pub struct Atomic<T: Primitive> {
+    inner: MaybeAtomic<T>,
+}
+
+impl<T: Primitive> Atomic<T> {
+    /// Consumes the atomic and returns the contained value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+
+    /// Loads a value from the atomic.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> T {
+        self.inner.load(order)
+    }
+
+    /// Stores a value into the atomic.
+    #[inline]
+    pub fn store(&self, val: T, order: Ordering) {
+        self.inner.store(val, order);
+    }
+
+    /// Stores a value into the atomic, returning the previous value.
+    #[inline]
+    pub fn swap(&self, val: T, order: Ordering) -> T {
+        self.inner.swap(val, order)
+    }
+
+    /// Stores `new` into the atomic if the current value is the same as `current`, returning
+    /// the previous value.
+    #[deprecated(note = "Use `compare_exchange` or `compare_exchange_weak` instead")]
+    #[inline]
+    pub fn compare_and_swap(&self, current: T, new: T, order: Ordering) -> T {
+        match self
+            .inner
+            .compare_exchange(current, new, order, failure_ordering(order))
+        {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
+
+    /// Stores `new` into the atomic if the current value is the same as `current`.
+    ///
+    /// The return value is `Ok` containing the previous value if the exchange took place, and
+    /// `Err` containing the current value otherwise.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        self.inner.compare_exchange(current, new, success, failure)
+    }
+
+    /// Stores `new` into the atomic if the current value is the same as `current`.
+    ///
+    /// Unlike `compare_exchange`, this function is allowed to spuriously fail even when the
+    /// comparison succeeds, which can result in more efficient code on some platforms.
+    #[inline]
+    pub fn compare_exchange_weak(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        self.inner
+            .compare_exchange_weak(current, new, success, failure)
+    }
+
+    /// Fetches the value, and applies a function to it that returns an optional new value.
+    ///
+    /// Returns `Ok` containing the previous value if the function returned `Some`, and `Err`
+    /// containing the previous value otherwise.
+    #[inline]
+    pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        self.inner.fetch_update(set_order, fetch_order, f)
+    }
+
+    /// Fetches the value, and applies a function to it that returns an optional new value.
+    ///
+    /// This is the same as [`fetch_update`](Self::fetch_update).
+    #[inline]
+    pub fn try_update<F>(&self, set_order: Ordering, fetch_order: Ordering, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        self.inner.fetch_update(set_order, fetch_order, f)
+    }
+
+    /// Fetches the value, applies a function to it and stores the result, returning the
+    /// previous value.
+    #[inline]
+    pub fn update<F>(&self, set_order: Ordering, fetch_order: Ordering, mut f: F) -> T
+    where
+        F: FnMut(T) -> T,
+    {
+        match self
+            .inner
+            .fetch_update(set_order, fetch_order, |val| Some(f(val)))
+        {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
+}
+
+impl<T: Primitive> Atomic<T>
+where
+    T::DefaultMode: MutBackend<T>,
+{
+    /// Returns a mutable reference to the underlying value.
+    ///
+    /// See [`MutBackend`] for the modes that support this.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+impl<T: Primitive> Atomic<T>
+where
+    T::DefaultMode: PtrBackend<T>,
+{
+    /// Returns a mutable pointer to the underlying value.
+    ///
+    /// See [`PtrBackend`] for the modes that support this.
+    #[inline]
+    pub fn as_ptr(&self) -> *mut T {
+        self.inner.as_ptr()
+    }
+}
+
+impl<T: Primitive> From<T> for Atomic<T> {
+    #[inline]
+    fn from(val: T) -> Self {
+        Self {
+            inner: MaybeAtomic::from(val),
+        }
+    }
+}
+
+impl<T: Primitive + fmt::Debug> fmt::Debug for Atomic<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+macro_rules! atomic_type {
+    (int $($name: ident: $prim: ty),*) => {
+        $(
+            atomic_type! {$name: $prim, <$prim>::default()}
+
+            impl Atomic<$prim> {
+                atomic_type! {
+                    @rmw $prim,
+                    /// Adds to the current value, returning the previous value.
+                    ///
+                    /// This operation wraps around on overflow.
+                    fetch_add
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Subtracts from the current value, returning the previous value.
+                    ///
+                    /// This operation wraps around on overflow.
+                    fetch_sub
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Bitwise "and" with the current value, returning the previous value.
+                    fetch_and
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Bitwise "nand" with the current value, returning the previous value.
+                    fetch_nand
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Bitwise "or" with the current value, returning the previous value.
+                    fetch_or
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Bitwise "xor" with the current value, returning the previous value.
+                    fetch_xor
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Maximum with the current value, returning the previous value.
+                    fetch_max
+                }
+
+                atomic_type! {
+                    @rmw $prim,
+                    /// Minimum with the current value, returning the previous value.
+                    fetch_min
+                }
+            }
+        )*
+    };
+    (@rmw $prim: ty, $(#[$attr: meta])* $name: ident) => {
+        $(#[$attr])*
+        #[inline]
+        pub fn $name(&self, val: $prim, order: Ordering) -> $prim {
+            self.inner.$name(val, order)
+        }
+    };
+    (@new $prim: ty) => {
+        /// Creates a new atomic.
+        #[cfg(not(any(loom, feature = "shuttle")))]
+        #[inline]
+        pub const fn new(val: $prim) -> Self {
+            Self {
+                inner: MaybeAtomic::<$prim>::new_const(val),
+            }
+        }
+
+        /// Creates a new atomic.
+        #[cfg(any(loom, feature = "shuttle"))]
+        #[inline]
+        pub fn new(val: $prim) -> Self {
+            Self {
+                inner: MaybeAtomic::<$prim>::new_const(val),
+            }
+        }
+    };
+    ($name: ident: $prim: ty, $default: expr) => {
+        doc_comment! {
+            concat!(
+                "A drop-in replacement for `core::sync::atomic::",
+                stringify!($name),
+                "`."
+            ),
+            pub type $name = Atomic<$prim>;
+        }
+
+        impl Atomic<$prim> {
+            atomic_type! {@new $prim}
+        }
+
+        impl Default for Atomic<$prim> {
+            #[inline]
+            fn default() -> Self {
+                Self::from($default)
+            }
+        }
+    };
+}
+
+atomic_type! {
+    int
+    AtomicU8: u8, AtomicU16: u16, AtomicU32: u32, AtomicU64: u64, AtomicUsize: usize,
+    AtomicU128: u128, AtomicI8: i8, AtomicI16: i16, AtomicI32: i32, AtomicI64: i64,
+    AtomicIsize: isize, AtomicI128: i128
+}
+
+atomic_type! {AtomicBool: bool, false}
+
+impl Atomic<bool> {
+    atomic_type! {
+        @rmw bool,
+        /// Logical "and" with the current value, returning the previous value.
+        fetch_and
+    }
+
+    atomic_type! {
+        @rmw bool,
+        /// Logical "nand" with the current value, returning the previous value.
+        fetch_nand
+    }
+
+    atomic_type! {
+        @rmw bool,
+        /// Logical "or" with the current value, returning the previous value.
+        fetch_or
+    }
+
+    atomic_type! {
+        @rmw bool,
+        /// Logical "xor" with the current value, returning the previous value.
+        fetch_xor
+    }
+
+    /// Logical "not" with the current value, returning the previous value.
+    #[inline]
+    pub fn fetch_not(&self, order: Ordering) -> bool {
+        self.inner.fetch_not(order)
+    }
+}
+
+/// A drop-in replacement for `core::sync::atomic::AtomicPtr`.
+pub type AtomicPtr<T> = Atomic<*mut T>;
+
+impl<T> Atomic<*mut T> {
+    atomic_type! {@new *mut T}
+
+    /// Offsets the pointer by `val` elements of `T`, returning the previous pointer.
+    ///
+    /// This operation wraps around, and is implemented with a compare-exchange loop in atomic
+    /// mode.
+    #[inline]
+    pub fn fetch_ptr_add(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.wrapping_add(val))
+    }
+
+    /// Offsets the pointer by `-val` elements of `T`, returning the previous pointer.
+    ///
+    /// This operation wraps around, and is implemented with a compare-exchange loop in atomic
+    /// mode.
+    #[inline]
+    pub fn fetch_ptr_sub(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.wrapping_sub(val))
+    }
+
+    /// Offsets the pointer by `val` bytes, returning the previous pointer.
+    ///
+    /// This operation wraps around, and is implemented with a compare-exchange loop in atomic
+    /// mode.
+    #[inline]
+    pub fn fetch_byte_add(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.wrapping_byte_add(val))
+    }
+
+    /// Offsets the pointer by `-val` bytes, returning the previous pointer.
+    ///
+    /// This operation wraps around, and is implemented with a compare-exchange loop in atomic
+    /// mode.
+    #[inline]
+    pub fn fetch_byte_sub(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.wrapping_byte_sub(val))
+    }
+
+    /// Bitwise "or" with the address of the pointer, returning the previous pointer.
+    ///
+    /// This operation keeps the provenance of the pointer, and is implemented with a
+    /// compare-exchange loop in atomic mode.
+    #[inline]
+    pub fn fetch_or(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.map_addr(|addr| addr | val))
+    }
+
+    /// Bitwise "and" with the address of the pointer, returning the previous pointer.
+    ///
+    /// This operation keeps the provenance of the pointer, and is implemented with a
+    /// compare-exchange loop in atomic mode.
+    #[inline]
+    pub fn fetch_and(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.map_addr(|addr| addr & val))
+    }
+
+    /// Bitwise "xor" with the address of the pointer, returning the previous pointer.
+    ///
+    /// This operation keeps the provenance of the pointer, and is implemented with a
+    /// compare-exchange loop in atomic mode.
+    #[inline]
+    pub fn fetch_xor(&self, val: usize, order: Ordering) -> *mut T {
+        self.fetch_ptr_op(order, |ptr| ptr.map_addr(|addr| addr ^ val))
+    }
+
+    #[inline]
+    fn fetch_ptr_op(&self, order: Ordering, op: impl Fn(*mut T) -> *mut T) -> *mut T {
+        match self
+            .inner
+            .fetch_update(order, failure_ordering(order), |ptr| Some(op(ptr)))
+        {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
+}
+
+impl<T> Default for Atomic<*mut T> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(ptr::null_mut())
+    }
+}
@@ -0,0 +1,36 @@
+// MIT + Apache 2.0
+
+//! Checks that `MaybeAtomicArray` gives access to its elements in every mode.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+use core::sync::atomic::Ordering;
+use maybe_atomic::{
+    generic::{MaybeAtomic, MaybeAtomicArray},
+    mode::IntBackend,
+};
+
+fn elements<M: IntBackend<u32>>() {
+    let array = MaybeAtomicArray::<u32, 3, M>::new([1, 2, 3]);
+    assert_eq!(array[1].fetch_add(5, Ordering::AcqRel), 2);
+    assert_eq!(
+        array
+            .as_slice()
+            .iter()
+            .map(MaybeAtomic::get)
+            .collect::<Vec<_>>(),
+        [1, 7, 3]
+    );
+    for element in &array {
+        element.fetch_sub(1, Ordering::AcqRel);
+    }
+    assert_eq!(array.snapshot(Ordering::Acquire), [0, 6, 2]);
+    array.fill(4, Ordering::Release);
+    assert_eq!(array.iter().map(MaybeAtomic::get).sum::<u32>(), 12);
+    assert_eq!(array.into_inner(), [4; 3]);
+}
+
+test_each_mode! {elements}
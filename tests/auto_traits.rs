@@ -0,0 +1,97 @@
+// MIT + Apache 2.0
+
+//! Pins which types are `Send` and `Sync` in each mode.
+//!
+//! Every assertion is checked at compile time, so this file only has to build.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+use core::{cell::Cell, num::NonZeroU32};
+#[cfg(feature = "critical-section")]
+use maybe_atomic::mode::CriticalSection;
+use maybe_atomic::{
+    generic::*,
+    if_sync, if_unsync,
+    marker::{MaybeSend, MaybeSync},
+    mode::{Atomic, Unsync},
+    sync::atomic,
+};
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+/// Calls `$assert` with every type in the `generic` module in the mode `$mode`, or the default
+/// mode if it's left out, except for `MaybeAtomicPtr`, which isn't `Send` in every mode.
+macro_rules! each_type {
+    ($assert: ident, $($mode: ty)?: $($trait: path),+) => {
+        $assert!(MaybeAtomicBool<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicU8<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicU16<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicU32<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicU64<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicU128<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicUsize<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicI8<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicI16<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicI32<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicI64<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicI128<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicIsize<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicF32<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicF64<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroU8<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroU16<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroU32<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroU64<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroU128<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroUsize<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroI8<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroI16<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroI32<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroI64<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroI128<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicNonZeroIsize<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicChar<$($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicOption<NonZeroU32, $($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicCell<u32, $($mode)?>: $($trait),+);
+        $assert!(MaybeAtomicArray<u32, 4, $($mode)?>: $($trait),+);
+    };
+}
+
+// Atomic mode.
+each_type!(assert_impl_all, Atomic: Send, Sync);
+assert_impl_all!(MaybeAtomicPtr<u8, Atomic>: Send, Sync);
+
+// Unsync mode.
+each_type!(assert_impl_all, Unsync: Send);
+each_type!(assert_not_impl_any, Unsync: Sync);
+assert_not_impl_any!(MaybeAtomicPtr<u8, Unsync>: Send, Sync);
+
+// CriticalSection mode, where only the pointer is neither.
+#[cfg(feature = "critical-section")]
+mod critical_section {
+    use super::*;
+
+    each_type!(assert_impl_all, CriticalSection: Send, Sync);
+    assert_not_impl_any!(MaybeAtomicPtr<u8, CriticalSection>: Send, Sync);
+}
+
+// The default modes, whatever they are.
+each_type!(assert_impl_all,: MaybeSend, MaybeSync);
+assert_impl_all!(MaybeAtomicPtr<u8>: MaybeSend, MaybeSync);
+assert_impl_all!(atomic::AtomicBool: MaybeSend, MaybeSync);
+assert_impl_all!(atomic::AtomicU32: MaybeSend, MaybeSync);
+assert_impl_all!(atomic::AtomicU128: MaybeSend, MaybeSync);
+assert_impl_all!(atomic::AtomicPtr<u8>: MaybeSend, MaybeSync);
+
+if_sync! {
+    // `MaybeSend` and `MaybeSync` are `Send` and `Sync`.
+    assert_not_impl_any!(*mut u8: MaybeSend);
+    assert_not_impl_any!(Cell<u8>: MaybeSync);
+    each_type!(assert_impl_all,: Send, Sync);
+    assert_impl_all!(MaybeAtomicPtr<u8>: Send, Sync);
+}
+
+if_unsync! {
+    // `MaybeSend` and `MaybeSync` are implemented by every type.
+    assert_impl_all!(*mut u8: MaybeSend, MaybeSync);
+    assert_impl_all!(Cell<u8>: MaybeSend, MaybeSync);
+}
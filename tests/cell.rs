@@ -0,0 +1,81 @@
+// MIT + Apache 2.0
+
+//! Checks that `MaybeAtomicCell` behaves the same in every mode, including for types wider than
+//! 128 bits.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+use bytemuck::{Pod, Zeroable};
+use core::sync::atomic::Ordering;
+use maybe_atomic::{
+    generic::{CellValue, MaybeAtomicCell},
+    mode::{Atomic, CellBackend},
+};
+
+/// A type wider than 128 bits, which is stored behind a spinlock in atomic mode.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Wide([u64; 3]);
+
+impl CellValue for Wide {
+    type Repr = [u32; 6];
+}
+
+fn wide_cell<M: CellBackend<Wide>>() {
+    let cell = MaybeAtomicCell::<Wide, M>::default();
+    assert_eq!(cell.swap(Wide([1, 2, 3]), Ordering::AcqRel), Wide([0; 3]));
+    assert_eq!(
+        cell.compare_exchange(
+            Wide([0; 3]),
+            Wide([4; 3]),
+            Ordering::AcqRel,
+            Ordering::Acquire
+        ),
+        Err(Wide([1, 2, 3]))
+    );
+    assert_eq!(
+        cell.compare_exchange(
+            Wide([1, 2, 3]),
+            Wide([4; 3]),
+            Ordering::AcqRel,
+            Ordering::Acquire
+        ),
+        Ok(Wide([1, 2, 3]))
+    );
+    assert_eq!(
+        cell.fetch_update(Ordering::AcqRel, Ordering::Acquire, |Wide([a, b, c])| {
+            Some(Wide([c, b, a + 1]))
+        }),
+        Ok(Wide([4; 3]))
+    );
+    assert_eq!(cell.take(), Wide([4, 4, 5]));
+    assert_eq!(cell.into_inner(), Wide([0; 3]));
+}
+
+test_each_mode! {wide_cell}
+
+#[test]
+fn wide_is_locked() {
+    assert!(!MaybeAtomicCell::<Wide, Atomic>::is_lock_free());
+}
+
+/// A `MaybeAtomicCell` holding a `char`, which is stored as a `u32` in atomic mode.
+type CharCell<M> = MaybeAtomicCell<char, M>;
+
+#[test]
+fn value_parity() {
+    assert_parity!(CharCell, 'a', |cell| {
+        assert_eq!(
+            cell.compare_exchange('a', 'é', Ordering::AcqRel, Ordering::Acquire),
+            Ok('a')
+        );
+        assert_eq!(
+            cell.fetch_update(Ordering::AcqRel, Ordering::Acquire, |_| None),
+            Err('é')
+        );
+        cell.into_inner()
+    });
+}
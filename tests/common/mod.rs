@@ -0,0 +1,80 @@
+// MIT + Apache 2.0
+
+//! Helpers shared by the integration tests. Each test crate uses a different subset of them.
+
+#![allow(dead_code, unused_macros)]
+
+use std::thread;
+
+/// The number of threads started by [`run_threads`].
+pub const THREADS: u32 = 8;
+
+/// The number of times each thread repeats an operation in the multithreaded tests.
+pub const ITERATIONS: u32 = 1000;
+
+/// Run `f` on [`THREADS`] threads at once, passing each one its index, and return the results
+/// in the order of the indices.
+pub fn run_threads<R: Send>(f: impl Fn(u32) -> R + Sync) -> Vec<R> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|i| {
+                let f = &f;
+                scope.spawn(move || f(i))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Evaluate `$op` with `$val` bound to a `$ty` created from `$init` in the atomic and non-atomic
+/// modes, and in the `CriticalSection` mode if the `critical-section` feature is enabled.
+/// Assert that `$op` gives the same result in every mode, and return that result.
+macro_rules! assert_parity {
+    ($ty: ident, $init: expr, |$val: ident| $op: expr) => {{
+        let atomic = {
+            let $val = $ty::<maybe_atomic::mode::Atomic>::from($init);
+            $op
+        };
+        let unsync = {
+            let $val = $ty::<maybe_atomic::mode::Unsync>::from($init);
+            $op
+        };
+        assert_eq!(atomic, unsync);
+        #[cfg(feature = "critical-section")]
+        assert_eq!(atomic, {
+            let $val = $ty::<maybe_atomic::mode::CriticalSection>::from($init);
+            $op
+        });
+        atomic
+    }};
+}
+
+/// Generate a module for each of the generic functions `$f`, with one test per mode calling
+/// `$f` with that mode. The `CriticalSection` test only exists if the `critical-section` feature
+/// is enabled.
+macro_rules! test_each_mode {
+    ($($f: ident),* $(,)?) => {
+        $(
+            mod $f {
+                #[test]
+                fn atomic() {
+                    super::$f::<maybe_atomic::mode::Atomic>();
+                }
+
+                #[test]
+                fn unsync() {
+                    super::$f::<maybe_atomic::mode::Unsync>();
+                }
+
+                #[test]
+                #[cfg(feature = "critical-section")]
+                fn critical_section() {
+                    super::$f::<maybe_atomic::mode::CriticalSection>();
+                }
+            }
+        )*
+    };
+}
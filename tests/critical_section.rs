@@ -0,0 +1,59 @@
+// MIT + Apache 2.0
+
+//! Checks the `CriticalSection` backend from several threads, using the `std` implementation of
+//! `critical-section`.
+
+#![cfg(all(not(any(loom, feature = "shuttle")), feature = "critical-section"))]
+
+mod common;
+
+use common::{run_threads, ITERATIONS, THREADS};
+use core::sync::atomic::Ordering;
+use maybe_atomic::{generic::MaybeAtomicU32, mode::CriticalSection};
+
+#[test]
+fn fetch_add_does_not_lose_updates() {
+    let counter = MaybeAtomicU32::<CriticalSection>::new(0);
+
+    run_threads(|_| {
+        for _ in 0..ITERATIONS {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    assert_eq!(counter.into_inner(), THREADS * ITERATIONS);
+}
+
+#[test]
+fn compare_exchange_does_not_lose_updates() {
+    let counter = MaybeAtomicU32::<CriticalSection>::new(0);
+
+    run_threads(|_| {
+        for _ in 0..ITERATIONS {
+            let mut current = counter.load(Ordering::Relaxed);
+            while let Err(actual) =
+                counter.compare_exchange(current, current + 1, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                current = actual;
+            }
+        }
+    });
+
+    assert_eq!(counter.into_inner(), THREADS * ITERATIONS);
+}
+
+#[test]
+fn compare_exchange_has_one_winner() {
+    let flag = MaybeAtomicU32::<CriticalSection>::new(0);
+
+    let winners = run_threads(|i| {
+        flag.compare_exchange(0, i + 1, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    })
+    .into_iter()
+    .filter(|&won| won)
+    .count();
+
+    assert_eq!(winners, 1);
+    assert_ne!(flag.into_inner(), 0);
+}
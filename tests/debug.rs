@@ -0,0 +1,67 @@
+// MIT + Apache 2.0
+
+//! Checks that `Debug` prints the value in the same way in every mode, and in the same way as the
+//! atomic types in `core`.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+use core::{
+    num::NonZeroU16,
+    sync::atomic::{AtomicBool, AtomicI64, AtomicPtr, AtomicU32},
+};
+use maybe_atomic::generic::{
+    MaybeAtomicArray, MaybeAtomicBool, MaybeAtomicCell, MaybeAtomicChar, MaybeAtomicF32,
+    MaybeAtomicI64, MaybeAtomicNonZeroU16, MaybeAtomicOption, MaybeAtomicPtr, MaybeAtomicU128,
+    MaybeAtomicU32,
+};
+
+/// Format `$init` wrapped in a `$ty` in every mode, asserting that the output is the same, and
+/// return it.
+macro_rules! debug {
+    ($ty: ident, $init: expr) => {
+        assert_parity!($ty, $init, |val| format!("{:?}", val))
+    };
+}
+
+type BytePtr<M> = MaybeAtomicPtr<u8, M>;
+type OptionU16<M> = MaybeAtomicOption<NonZeroU16, M>;
+type F64Cell<M> = MaybeAtomicCell<f64, M>;
+type U32Array<M> = MaybeAtomicArray<u32, 3, M>;
+
+#[test]
+fn primitives_match_core() {
+    assert_eq!(
+        debug!(MaybeAtomicBool, true),
+        format!("{:?}", AtomicBool::new(true))
+    );
+    assert_eq!(
+        debug!(MaybeAtomicU32, 7),
+        format!("{:?}", AtomicU32::new(7))
+    );
+    assert_eq!(
+        debug!(MaybeAtomicI64, -7),
+        format!("{:?}", AtomicI64::new(-7))
+    );
+
+    let mut byte = 0u8;
+    let ptr = &mut byte as *mut u8;
+    assert_eq!(debug!(BytePtr, ptr), format!("{:?}", AtomicPtr::new(ptr)));
+}
+
+#[test]
+fn values_match() {
+    assert_eq!(
+        debug!(MaybeAtomicU128, u128::MAX),
+        format!("{:?}", u128::MAX)
+    );
+    assert_eq!(debug!(MaybeAtomicF32, -0.5), "-0.5");
+    assert_eq!(debug!(MaybeAtomicChar, 'é'), "'é'");
+    assert_eq!(debug!(MaybeAtomicNonZeroU16, NonZeroU16::MAX), "65535");
+    assert_eq!(debug!(OptionU16, NonZeroU16::new(3)), "Some(3)");
+    assert_eq!(debug!(OptionU16, None), "None");
+    assert_eq!(debug!(F64Cell, 1e100), "1e100");
+    assert_eq!(debug!(U32Array, [1, 2, 3]), "[1, 2, 3]");
+}
@@ -0,0 +1,65 @@
+// MIT + Apache 2.0
+
+//! Checks that `as_ptr`, `as_atomic` and `as_cell` expose the storage of the value.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+#[cfg(not(feature = "portable-atomic"))]
+use core::sync::atomic::AtomicU32;
+use core::{cell::Cell, ptr, sync::atomic::Ordering};
+use maybe_atomic::{
+    generic::{MaybeAtomicF32, MaybeAtomicU32},
+    mode::{Atomic, PtrBackend, Unsync},
+};
+#[cfg(feature = "portable-atomic")]
+use portable_atomic::AtomicU32;
+
+/// Stands in for an API that takes the atomic type the `Atomic` mode uses.
+fn increment(counter: &AtomicU32) {
+    counter.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Stands in for an API that takes a `Cell`.
+fn double(cell: &Cell<u32>) {
+    cell.set(cell.get() * 2);
+}
+
+#[test]
+fn as_atomic() {
+    let counter = MaybeAtomicU32::<Atomic>::new(1);
+    increment(counter.as_atomic());
+    assert_eq!(counter.load(Ordering::Acquire), 2);
+
+    let ratio = MaybeAtomicF32::<Atomic>::new(0.5);
+    assert_eq!(ratio.as_atomic().load(Ordering::Acquire), 0.5f32.to_bits());
+}
+
+#[test]
+fn as_cell() {
+    let counter = MaybeAtomicU32::<Unsync>::new(3);
+    double(counter.as_cell());
+    assert_eq!(counter.load(Ordering::Acquire), 6);
+}
+
+#[test]
+fn as_ptr_points_at_the_storage() {
+    let atomic = MaybeAtomicU32::<Atomic>::new(0);
+    assert!(ptr::eq(atomic.as_ptr(), atomic.as_atomic().as_ptr()));
+
+    let float = MaybeAtomicF32::<Atomic>::new(0.0);
+    assert!(ptr::eq(float.as_ptr().cast(), float.as_atomic().as_ptr()));
+
+    let unsync = MaybeAtomicU32::<Unsync>::new(0);
+    assert!(ptr::eq(unsync.as_ptr(), unsync.as_cell().as_ptr()));
+}
+
+fn as_ptr_is_stable<M: PtrBackend<u32>>() {
+    let counter = MaybeAtomicU32::<M>::new(0);
+    assert!(!counter.as_ptr().is_null());
+    assert!(ptr::eq(counter.as_ptr(), counter.as_ptr()));
+}
+
+test_each_mode! {as_ptr_is_stable}
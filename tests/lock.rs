@@ -0,0 +1,111 @@
+// MIT + Apache 2.0
+
+//! Checks the spinlock that protects 128-bit types in atomic mode without `portable-atomic`.
+
+#![cfg(not(any(loom, feature = "shuttle", feature = "portable-atomic")))]
+
+mod common;
+
+use common::{run_threads, ITERATIONS, THREADS};
+use core::sync::atomic::Ordering;
+use maybe_atomic::{
+    generic::{MaybeAtomicI128, MaybeAtomicU128},
+    mode::Atomic,
+};
+
+/// The number of updates made by all of the threads together.
+const UPDATES: u128 = (THREADS * ITERATIONS) as u128;
+
+/// A value whose 32-bit parts are all different.
+const BITS: u128 = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+
+#[test]
+fn is_not_lock_free() {
+    assert!(!MaybeAtomicU128::<Atomic>::is_lock_free());
+}
+
+#[test]
+fn fetch_add_carries_across_parts() {
+    // Every addition carries out of the low 64 bits while the other threads are adding too.
+    let start = u64::MAX as u128 - UPDATES / 2;
+    let counter = MaybeAtomicU128::<Atomic>::new(start);
+
+    run_threads(|_| {
+        for _ in 0..ITERATIONS {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    assert_eq!(counter.load(Ordering::Relaxed), start + UPDATES);
+    assert_eq!(
+        counter.fetch_add(u128::MAX, Ordering::Relaxed),
+        start + UPDATES
+    );
+    assert_eq!(counter.load(Ordering::Relaxed), start + UPDATES - 1);
+}
+
+#[test]
+fn fetch_sub_borrows_across_parts() {
+    let counter = MaybeAtomicI128::<Atomic>::new(0);
+    assert_eq!(counter.fetch_sub(1, Ordering::Relaxed), 0);
+    assert_eq!(counter.load(Ordering::Relaxed), -1);
+    assert_eq!(counter.fetch_add(1 << 96, Ordering::Relaxed), -1);
+    assert_eq!(counter.load(Ordering::Relaxed), (1 << 96) - 1);
+    assert_eq!(
+        counter.fetch_sub(i128::MAX, Ordering::Relaxed),
+        (1 << 96) - 1
+    );
+    assert_eq!(
+        counter.into_inner(),
+        ((1i128 << 96) - 1).wrapping_sub(i128::MAX)
+    );
+}
+
+#[test]
+fn compare_exchange_round_trips() {
+    let val = MaybeAtomicU128::<Atomic>::new(BITS);
+
+    assert_eq!(
+        val.compare_exchange(BITS, !BITS, Ordering::AcqRel, Ordering::Acquire),
+        Ok(BITS)
+    );
+    assert_eq!(val.load(Ordering::Acquire), !BITS);
+    assert_eq!(
+        val.compare_exchange(BITS, 0, Ordering::AcqRel, Ordering::Acquire),
+        Err(!BITS)
+    );
+    // Only the highest part differs from the current value.
+    assert_eq!(
+        val.compare_exchange(!BITS ^ (1 << 127), 0, Ordering::AcqRel, Ordering::Acquire),
+        Err(!BITS)
+    );
+    assert_eq!(
+        val.compare_exchange_weak(!BITS, BITS, Ordering::SeqCst, Ordering::SeqCst),
+        Ok(!BITS)
+    );
+    assert_eq!(val.into_inner(), BITS);
+}
+
+#[test]
+fn compare_exchange_does_not_lose_updates() {
+    let counter = MaybeAtomicU128::<Atomic>::new(u32::MAX as u128);
+
+    run_threads(|_| {
+        for _ in 0..ITERATIONS {
+            let mut current = counter.load(Ordering::Relaxed);
+            while let Err(actual) = counter.compare_exchange_weak(
+                current,
+                current + (1 << 32),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                current = actual;
+            }
+        }
+    });
+
+    assert_eq!(
+        counter.load(Ordering::Relaxed),
+        u32::MAX as u128 + (UPDATES << 32)
+    );
+}
@@ -0,0 +1,86 @@
+// MIT + Apache 2.0
+
+//! Model checks for the atomic backend.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --test loom --release`.
+
+#![cfg(loom)]
+
+use core::sync::atomic::Ordering;
+use loom::{sync::Arc, thread};
+use maybe_atomic::{MaybeAtomicBool, MaybeAtomicUsize};
+
+#[test]
+fn fetch_add_does_not_lose_updates() {
+    loom::model(|| {
+        let counter = Arc::new(MaybeAtomicUsize::new(0));
+
+        let other = counter.clone();
+        let handle = thread::spawn(move || {
+            other.fetch_add(1, Ordering::Relaxed);
+        });
+        counter.fetch_add(1, Ordering::Relaxed);
+        handle.join().unwrap();
+
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+    });
+}
+
+#[test]
+fn compare_exchange_has_one_winner() {
+    loom::model(|| {
+        let flag = Arc::new(MaybeAtomicBool::new(false));
+
+        let other = flag.clone();
+        let handle = thread::spawn(move || {
+            other
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        });
+        let won = flag
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok();
+        let other_won = handle.join().unwrap();
+
+        assert!(won ^ other_won);
+    });
+}
+
+#[test]
+fn fetch_update_does_not_lose_updates() {
+    loom::model(|| {
+        let counter = Arc::new(MaybeAtomicUsize::new(0));
+
+        let other = counter.clone();
+        let handle = thread::spawn(move || {
+            other
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(x * 2 + 1))
+                .unwrap();
+        });
+        counter
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(x * 2 + 1))
+            .unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+    });
+}
+
+#[test]
+fn release_store_is_visible_after_acquire_load() {
+    loom::model(|| {
+        let data = Arc::new(MaybeAtomicUsize::new(0));
+        let ready = Arc::new(MaybeAtomicBool::new(false));
+
+        let (other_data, other_ready) = (data.clone(), ready.clone());
+        let handle = thread::spawn(move || {
+            other_data.store(42, Ordering::Relaxed);
+            other_ready.store(true, Ordering::Release);
+        });
+
+        if ready.load(Ordering::Acquire) {
+            assert_eq!(data.load(Ordering::Relaxed), 42);
+        }
+        handle.join().unwrap();
+    });
+}
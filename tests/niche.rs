@@ -0,0 +1,210 @@
+// MIT + Apache 2.0
+
+//! Checks that the niche types keep their invariant in every mode.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+use core::{
+    mem,
+    num::{NonZeroI128, NonZeroI8, NonZeroU32, NonZeroU64},
+    sync::atomic::Ordering,
+};
+use maybe_atomic::{
+    generic::{
+        MaybeAtomicChar, MaybeAtomicNonZeroI128, MaybeAtomicNonZeroI8, MaybeAtomicNonZeroU32,
+        MaybeAtomicNonZeroU64, MaybeAtomicOption,
+    },
+    mode::{Atomic, Backend, Unsync},
+};
+
+fn nonzero(val: u32) -> NonZeroU32 {
+    NonZeroU32::new(val).unwrap()
+}
+
+#[test]
+fn option_none_is_zero() {
+    // `MaybeAtomicOption` is `repr(transparent)` over an atomic or a `Cell`, which both have
+    // the same layout as the integer inside.
+    let none = MaybeAtomicOption::<NonZeroU32, Atomic>::new(None);
+    assert_eq!(
+        unsafe { mem::transmute::<MaybeAtomicOption<NonZeroU32, Atomic>, u32>(none) },
+        0
+    );
+    let none = MaybeAtomicOption::<NonZeroU32, Unsync>::new(None);
+    assert_eq!(
+        unsafe { mem::transmute::<MaybeAtomicOption<NonZeroU32, Unsync>, u32>(none) },
+        0
+    );
+
+    let zero = unsafe { mem::transmute::<u32, MaybeAtomicOption<NonZeroU32, Atomic>>(0) };
+    assert_eq!(zero.into_inner(), None);
+    let zero = unsafe { mem::transmute::<u32, MaybeAtomicOption<NonZeroU32, Unsync>>(0) };
+    assert_eq!(zero.into_inner(), None);
+
+    let some = MaybeAtomicOption::<NonZeroU32, Atomic>::new(Some(nonzero(7)));
+    assert_eq!(
+        unsafe { mem::transmute::<MaybeAtomicOption<NonZeroU32, Atomic>, u32>(some) },
+        7
+    );
+}
+
+fn option<M: Backend<u32>>() {
+    let val = MaybeAtomicOption::<NonZeroU32, M>::default();
+    assert_eq!(val.load(Ordering::Relaxed), None);
+
+    assert_eq!(
+        val.compare_exchange(
+            Some(nonzero(1)),
+            Some(nonzero(2)),
+            Ordering::AcqRel,
+            Ordering::Acquire
+        ),
+        Err(None)
+    );
+    assert_eq!(
+        val.compare_exchange(None, Some(nonzero(1)), Ordering::AcqRel, Ordering::Acquire),
+        Ok(None)
+    );
+    assert_eq!(
+        val.compare_exchange(None, Some(nonzero(2)), Ordering::AcqRel, Ordering::Acquire),
+        Err(Some(nonzero(1)))
+    );
+    assert_eq!(
+        val.swap(Some(nonzero(3)), Ordering::AcqRel),
+        Some(nonzero(1))
+    );
+
+    assert_eq!(val.take(), Some(nonzero(3)));
+    assert_eq!(val.take(), None);
+    assert_eq!(val.load(Ordering::Relaxed), None);
+
+    val.store(Some(nonzero(u32::MAX)), Ordering::Release);
+    // Returning `Some(None)` stores `None`, while returning `None` stores nothing.
+    assert_eq!(
+        val.fetch_update(Ordering::AcqRel, Ordering::Acquire, |_| Some(None)),
+        Ok(Some(nonzero(u32::MAX)))
+    );
+    assert_eq!(val.load(Ordering::Relaxed), None);
+    assert_eq!(
+        val.fetch_update(Ordering::AcqRel, Ordering::Acquire, |_| None),
+        Err(None)
+    );
+    assert_eq!(
+        val.fetch_update(Ordering::AcqRel, Ordering::Acquire, |prev| {
+            assert_eq!(prev, None);
+            Some(Some(nonzero(5)))
+        }),
+        Ok(None)
+    );
+    assert_eq!(val.swap(None, Ordering::AcqRel), Some(nonzero(5)));
+    assert_eq!(val.into_inner(), None);
+
+    let val = MaybeAtomicOption::<NonZeroU32, M>::from(Some(nonzero(9)));
+    assert_eq!(val.into_inner(), Some(nonzero(9)));
+}
+
+fn nonzero_ints<M: Backend<u32>>() {
+    let val = MaybeAtomicNonZeroU32::<M>::from(nonzero(1));
+    assert_eq!(val.swap(nonzero(u32::MAX), Ordering::AcqRel), nonzero(1));
+    assert_eq!(
+        val.compare_exchange(nonzero(1), nonzero(2), Ordering::AcqRel, Ordering::Acquire),
+        Err(nonzero(u32::MAX))
+    );
+    assert_eq!(
+        val.compare_exchange(
+            nonzero(u32::MAX),
+            nonzero(2),
+            Ordering::AcqRel,
+            Ordering::Acquire
+        ),
+        Ok(nonzero(u32::MAX))
+    );
+    assert_eq!(
+        val.fetch_update(Ordering::AcqRel, Ordering::Acquire, |prev| NonZeroU32::new(
+            prev.get() - 2
+        )),
+        Err(nonzero(2))
+    );
+    assert_eq!(val.into_inner(), nonzero(2));
+}
+
+fn chars<M: Backend<u32>>() {
+    let val = MaybeAtomicChar::<M>::default();
+    assert_eq!(val.swap('a', Ordering::AcqRel), '\0');
+    assert_eq!(
+        val.compare_exchange('b', 'c', Ordering::AcqRel, Ordering::Acquire),
+        Err('a')
+    );
+    assert_eq!(
+        val.compare_exchange('a', char::MAX, Ordering::AcqRel, Ordering::Acquire),
+        Ok('a')
+    );
+    assert_eq!(
+        val.compare_exchange_weak(char::MAX, '\u{d7ff}', Ordering::SeqCst, Ordering::SeqCst),
+        Ok(char::MAX)
+    );
+    // The next value after U+D7FF is a surrogate, which isn't a `char`.
+    assert_eq!(
+        val.fetch_update(Ordering::AcqRel, Ordering::Acquire, |prev| {
+            char::from_u32(prev as u32 + 1)
+        }),
+        Err('\u{d7ff}')
+    );
+    assert_eq!(val.take(), '\u{d7ff}');
+    assert_eq!(val.into_inner(), '\0');
+}
+
+test_each_mode! {option, nonzero_ints, chars}
+
+#[test]
+fn nonzero_other_widths() {
+    let (min, max) = (
+        NonZeroI8::new(i8::MIN).unwrap(),
+        NonZeroI8::new(-1).unwrap(),
+    );
+    let atomic = MaybeAtomicNonZeroI8::<Atomic>::new(min);
+    let unsync = MaybeAtomicNonZeroI8::<Unsync>::new(min);
+    assert_eq!(atomic.swap(max, Ordering::AcqRel), min);
+    assert_eq!(unsync.swap(max, Ordering::AcqRel), min);
+    assert_eq!(
+        atomic.compare_exchange(min, max, Ordering::AcqRel, Ordering::Acquire),
+        Err(max)
+    );
+    assert_eq!(
+        unsync.compare_exchange(min, max, Ordering::AcqRel, Ordering::Acquire),
+        Err(max)
+    );
+
+    let big = NonZeroU64::new(u64::MAX).unwrap();
+    let atomic = MaybeAtomicNonZeroU64::<Atomic>::new(big);
+    let unsync = MaybeAtomicNonZeroU64::<Unsync>::new(big);
+    assert_eq!(
+        atomic.compare_exchange(big, big, Ordering::AcqRel, Ordering::Acquire),
+        Ok(big)
+    );
+    assert_eq!(
+        unsync.compare_exchange(big, big, Ordering::AcqRel, Ordering::Acquire),
+        Ok(big)
+    );
+
+    let (neg, pos) = (
+        NonZeroI128::new(-1).unwrap(),
+        NonZeroI128::new(i128::MAX).unwrap(),
+    );
+    let atomic = MaybeAtomicNonZeroI128::<Atomic>::new(neg);
+    let unsync = MaybeAtomicNonZeroI128::<Unsync>::new(neg);
+    assert_eq!(atomic.swap(pos, Ordering::AcqRel), neg);
+    assert_eq!(unsync.swap(pos, Ordering::AcqRel), neg);
+    assert_eq!(
+        atomic.compare_exchange(pos, neg, Ordering::AcqRel, Ordering::Acquire),
+        Ok(pos)
+    );
+    assert_eq!(
+        unsync.compare_exchange(pos, neg, Ordering::AcqRel, Ordering::Acquire),
+        Ok(pos)
+    );
+    assert_eq!(atomic.into_inner(), unsync.into_inner());
+}
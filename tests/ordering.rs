@@ -0,0 +1,145 @@
+// MIT + Apache 2.0
+
+//! Checks that every backend rejects invalid orderings in the same way as `core`.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+use core::sync::atomic::Ordering;
+use maybe_atomic::generic::{MaybeAtomicU128, MaybeAtomicU32};
+use std::panic::{self, AssertUnwindSafe};
+
+const ORDERINGS: [Ordering; 5] = [
+    Ordering::Relaxed,
+    Ordering::Release,
+    Ordering::Acquire,
+    Ordering::AcqRel,
+    Ordering::SeqCst,
+];
+
+/// Run `f`, returning its panic message if it panicked.
+fn panic_message(f: impl FnOnce()) -> Option<String> {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).err()?;
+    match payload.downcast::<&'static str>() {
+        Ok(message) => Some(message.to_string()),
+        Err(payload) => Some(*payload.downcast::<String>().unwrap()),
+    }
+}
+
+/// Assert that `$op` panics with the same message in every mode, or doesn't panic in any,
+/// returning the message.
+macro_rules! panic_parity {
+    ($ty: ident, |$val: ident| $op: expr) => {
+        assert_parity!($ty, 0, |$val| panic_message(|| {
+            let _ = $op;
+        }))
+    };
+}
+
+#[test]
+fn load() {
+    for &order in &ORDERINGS {
+        let message = panic_parity!(MaybeAtomicU32, |val| val.load(order));
+        panic_parity!(MaybeAtomicU128, |val| val.load(order));
+
+        let expected = match order {
+            Ordering::Release => Some("there is no such thing as a release load"),
+            Ordering::AcqRel => Some("there is no such thing as an acquire-release load"),
+            _ => None,
+        };
+        assert_eq!(message.as_deref(), expected);
+    }
+}
+
+#[test]
+fn store() {
+    for &order in &ORDERINGS {
+        let message = panic_parity!(MaybeAtomicU32, |val| val.store(1, order));
+        panic_parity!(MaybeAtomicU128, |val| val.store(1, order));
+
+        let expected = match order {
+            Ordering::Acquire => Some("there is no such thing as an acquire store"),
+            Ordering::AcqRel => Some("there is no such thing as an acquire-release store"),
+            _ => None,
+        };
+        assert_eq!(message.as_deref(), expected);
+    }
+}
+
+#[test]
+fn swap() {
+    for &order in &ORDERINGS {
+        assert_eq!(
+            panic_parity!(MaybeAtomicU32, |val| val.swap(1, order)),
+            None
+        );
+        assert_eq!(
+            panic_parity!(MaybeAtomicU128, |val| val.swap(1, order)),
+            None
+        );
+    }
+}
+
+#[test]
+fn compare_exchange() {
+    for &success in &ORDERINGS {
+        for &failure in &ORDERINGS {
+            // Check both the successful and the failing case.
+            for &current in &[0, 1] {
+                let message = panic_parity!(MaybeAtomicU32, |val| val
+                    .compare_exchange(current, 2, success, failure));
+                panic_parity!(MaybeAtomicU32, |val| val
+                    .compare_exchange_weak(current, 2, success, failure));
+                panic_parity!(MaybeAtomicU128, |val| val.compare_exchange(
+                    current as u128,
+                    2,
+                    success,
+                    failure
+                ));
+
+                let expected = match failure {
+                    Ordering::Release => {
+                        Some("there is no such thing as a release failure ordering")
+                    }
+                    Ordering::AcqRel => {
+                        Some("there is no such thing as an acquire-release failure ordering")
+                    }
+                    _ => None,
+                };
+                assert_eq!(message.as_deref(), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn fetch_update() {
+    for &set_order in &ORDERINGS {
+        for &fetch_order in &ORDERINGS {
+            let message = panic_parity!(MaybeAtomicU32, |val| val.fetch_update(
+                set_order,
+                fetch_order,
+                |x| Some(x + 1)
+            ));
+            panic_parity!(MaybeAtomicU32, |val| val.fetch_update(
+                set_order,
+                fetch_order,
+                |_| None
+            ));
+            panic_parity!(MaybeAtomicU128, |val| val.fetch_update(
+                set_order,
+                fetch_order,
+                |x| Some(x + 1)
+            ));
+
+            let expected = match fetch_order {
+                Ordering::Release => Some("there is no such thing as a release load"),
+                Ordering::AcqRel => Some("there is no such thing as an acquire-release load"),
+                _ => None,
+            };
+            assert_eq!(message.as_deref(), expected);
+        }
+    }
+}
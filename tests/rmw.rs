@@ -0,0 +1,197 @@
+// MIT + Apache 2.0
+
+//! Checks that the read-modify-write operations of every backend wrap around in the same way as
+//! `core`, and that booleans, floats and pointers behave the same in every mode.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+
+#[macro_use]
+mod common;
+
+use core::sync::atomic::Ordering;
+use maybe_atomic::generic::{
+    MaybeAtomicBool, MaybeAtomicF32, MaybeAtomicF64, MaybeAtomicI128, MaybeAtomicI16,
+    MaybeAtomicI32, MaybeAtomicI64, MaybeAtomicI8, MaybeAtomicIsize, MaybeAtomicPtr,
+    MaybeAtomicU128, MaybeAtomicU16, MaybeAtomicU32, MaybeAtomicU64, MaybeAtomicU8,
+    MaybeAtomicUsize,
+};
+
+/// Assert that the read-modify-write operation `$op` returns the same value and leaves the same
+/// value behind in every mode, returning both.
+macro_rules! rmw {
+    ($ty: ident, $init: expr, $op: ident($val: expr)) => {
+        assert_parity!($ty, $init, |val| (
+            val.$op($val, Ordering::Relaxed),
+            val.into_inner()
+        ))
+    };
+}
+
+macro_rules! int_tests {
+    ($($name: ident: $ty: ident($int: ident)),*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (min, max) = (<$int>::MIN, <$int>::MAX);
+
+                assert_eq!(rmw!($ty, max, fetch_add(1)), (max, min));
+                assert_eq!(rmw!($ty, max, fetch_add(max)), (max, max.wrapping_add(max)));
+                assert_eq!(rmw!($ty, min, fetch_sub(1)), (min, max));
+                assert_eq!(rmw!($ty, min, fetch_sub(max)), (min, min.wrapping_sub(max)));
+
+                for &(a, b) in &[(0, 0), (max, max), (min, max), (min, min), (max, 1)] {
+                    assert_eq!(rmw!($ty, a, fetch_and(b)), (a, a & b));
+                    assert_eq!(rmw!($ty, a, fetch_nand(b)), (a, !(a & b)));
+                    assert_eq!(rmw!($ty, a, fetch_or(b)), (a, a | b));
+                    assert_eq!(rmw!($ty, a, fetch_xor(b)), (a, a ^ b));
+                    assert_eq!(rmw!($ty, a, fetch_max(b)), (a, a.max(b)));
+                    assert_eq!(rmw!($ty, a, fetch_min(b)), (a, a.min(b)));
+                }
+            }
+        )*
+    };
+}
+
+int_tests! {
+    u8_wraps: MaybeAtomicU8(u8),
+    u16_wraps: MaybeAtomicU16(u16),
+    u32_wraps: MaybeAtomicU32(u32),
+    u64_wraps: MaybeAtomicU64(u64),
+    usize_wraps: MaybeAtomicUsize(usize),
+    u128_wraps: MaybeAtomicU128(u128),
+    i8_wraps: MaybeAtomicI8(i8),
+    i16_wraps: MaybeAtomicI16(i16),
+    i32_wraps: MaybeAtomicI32(i32),
+    i64_wraps: MaybeAtomicI64(i64),
+    isize_wraps: MaybeAtomicIsize(isize),
+    i128_wraps: MaybeAtomicI128(i128)
+}
+
+macro_rules! signed_tests {
+    ($($name: ident: $ty: ident($int: ident)),*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (min, max) = (<$int>::MIN, <$int>::MAX);
+
+                // Signed comparisons, which differ from comparing the bits as unsigned integers.
+                for &(a, b) in &[(-1, 1), (1, -1), (min, -1), (-1, min), (min, max), (0, min)] {
+                    assert_eq!(rmw!($ty, a, fetch_max(b)), (a, a.max(b)));
+                    assert_eq!(rmw!($ty, a, fetch_min(b)), (a, a.min(b)));
+                    assert_eq!(rmw!($ty, a, fetch_nand(b)), (a, !(a & b)));
+                }
+
+                assert_eq!(rmw!($ty, -1, fetch_nand(-1)), (-1, 0));
+                assert_eq!(rmw!($ty, 0, fetch_nand(0)), (0, -1));
+                assert_eq!(rmw!($ty, min, fetch_add(min)), (min, 0));
+                assert_eq!(rmw!($ty, -1, fetch_sub(max)), (-1, min));
+            }
+        )*
+    };
+}
+
+signed_tests! {
+    i8_signed: MaybeAtomicI8(i8),
+    i16_signed: MaybeAtomicI16(i16),
+    i32_signed: MaybeAtomicI32(i32),
+    i64_signed: MaybeAtomicI64(i64),
+    isize_signed: MaybeAtomicIsize(isize),
+    i128_signed: MaybeAtomicI128(i128)
+}
+
+#[test]
+fn bool_ops() {
+    for &(a, b) in &[(false, false), (false, true), (true, false), (true, true)] {
+        assert_eq!(rmw!(MaybeAtomicBool, a, fetch_and(b)), (a, a & b));
+        assert_eq!(rmw!(MaybeAtomicBool, a, fetch_nand(b)), (a, !(a & b)));
+        assert_eq!(rmw!(MaybeAtomicBool, a, fetch_or(b)), (a, a | b));
+        assert_eq!(rmw!(MaybeAtomicBool, a, fetch_xor(b)), (a, a ^ b));
+    }
+
+    for &a in &[false, true] {
+        assert_eq!(
+            assert_parity!(MaybeAtomicBool, a, |val| (
+                val.fetch_not(Ordering::Relaxed),
+                val.into_inner()
+            )),
+            (a, !a)
+        );
+    }
+}
+
+/// A `MaybeAtomicPtr` to a byte, so that it takes only the mode as a type parameter.
+type BytePtr<M> = MaybeAtomicPtr<u8, M>;
+
+#[test]
+fn ptr_round_trip() {
+    let mut bytes = [0u8; 2];
+    let (first, second) = (&mut bytes[0] as *mut u8, &mut bytes[1] as *mut u8);
+
+    assert_eq!(rmw!(BytePtr, first, swap(second)), (first, second));
+    assert_eq!(
+        assert_parity!(BytePtr, first, |val| (
+            val.compare_exchange(second, first, Ordering::AcqRel, Ordering::Acquire),
+            val.compare_exchange(first, second, Ordering::AcqRel, Ordering::Acquire),
+            val.load(Ordering::Acquire),
+            val.into_inner()
+        )),
+        (Err(first), Ok(first), second, second)
+    );
+}
+
+/// Like `rmw!`, but returns the bits of the floats, so that NaNs compare equal to themselves and
+/// `-0.0` doesn't compare equal to `0.0`.
+macro_rules! float_rmw {
+    ($ty: ident, $init: expr, $op: ident($val: expr)) => {
+        assert_parity!($ty, $init, |val| (
+            val.$op($val, Ordering::Relaxed).to_bits(),
+            val.into_inner().to_bits()
+        ))
+    };
+}
+
+macro_rules! float_tests {
+    ($($name: ident: $ty: ident($float: ident)),*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (max, nan, inf) = (<$float>::MAX, <$float>::NAN, <$float>::INFINITY);
+                let bits = |a: $float, b: $float| (a.to_bits(), b.to_bits());
+
+                assert_eq!(float_rmw!($ty, 1.5, fetch_add(2.25)), bits(1.5, 3.75));
+                assert_eq!(float_rmw!($ty, max, fetch_add(max)), bits(max, inf));
+                assert_eq!(float_rmw!($ty, 1.5, fetch_sub(2.25)), bits(1.5, -0.75));
+                assert_eq!(float_rmw!($ty, -max, fetch_sub(max)), bits(-max, -inf));
+                assert_eq!(float_rmw!($ty, 1.0, fetch_max(2.0)), bits(1.0, 2.0));
+                assert_eq!(float_rmw!($ty, 1.0, fetch_min(2.0)), bits(1.0, 1.0));
+
+                // Like `max` and `min`, a NaN is replaced by the other value.
+                assert_eq!(float_rmw!($ty, 1.0, fetch_max(nan)), bits(1.0, 1.0));
+                assert_eq!(float_rmw!($ty, nan, fetch_max(1.0)), bits(nan, 1.0));
+                assert_eq!(float_rmw!($ty, 1.0, fetch_min(nan)), bits(1.0, 1.0));
+                assert_eq!(float_rmw!($ty, nan, fetch_min(1.0)), bits(nan, 1.0));
+                assert!(<$float>::from_bits(float_rmw!($ty, inf, fetch_add(-inf)).1).is_nan());
+
+                // `compare_exchange` compares the bits, so `-0.0` isn't `0.0`, but a NaN is
+                // itself.
+                let cas = |init: $float, current: $float| {
+                    assert_parity!($ty, init, |val| (
+                        val.compare_exchange(current, 1.0, Ordering::AcqRel, Ordering::Acquire)
+                            .map(<$float>::to_bits)
+                            .map_err(<$float>::to_bits),
+                        val.into_inner().to_bits()
+                    ))
+                };
+                let (zero, neg_zero) = bits(0.0, -0.0);
+                assert_eq!(cas(-0.0, 0.0), (Err(neg_zero), neg_zero));
+                assert_eq!(cas(0.0, -0.0), (Err(zero), zero));
+                assert_eq!(cas(nan, nan), (Ok(nan.to_bits()), (1.0 as $float).to_bits()));
+            }
+        )*
+    };
+}
+
+float_tests! {
+    f32_ops: MaybeAtomicF32(f32),
+    f64_ops: MaybeAtomicF64(f64)
+}
@@ -0,0 +1,76 @@
+// MIT + Apache 2.0
+
+//! Randomized schedule checks for the atomic backend under shuttle.
+//!
+//! Run with `cargo test --features shuttle --test shuttle --release`.
+
+#![cfg(all(feature = "shuttle", not(loom)))]
+
+use core::sync::atomic::Ordering;
+use maybe_atomic::{MaybeAtomicBool, MaybeAtomicU128, MaybeAtomicUsize};
+use shuttle::{sync::Arc, thread};
+
+/// The number of random schedules each test explores.
+const ITERATIONS: usize = 1000;
+
+#[test]
+fn fetch_add_does_not_lose_updates() {
+    shuttle::check_random(
+        || {
+            let counter = Arc::new(MaybeAtomicUsize::new(0));
+
+            let other = counter.clone();
+            let handle = thread::spawn(move || {
+                other.fetch_add(1, Ordering::Relaxed);
+            });
+            counter.fetch_add(1, Ordering::Relaxed);
+            handle.join().unwrap();
+
+            assert_eq!(counter.load(Ordering::Relaxed), 2);
+        },
+        ITERATIONS,
+    );
+}
+
+#[test]
+fn compare_exchange_has_one_winner() {
+    shuttle::check_random(
+        || {
+            let flag = Arc::new(MaybeAtomicBool::new(false));
+
+            let other = flag.clone();
+            let handle = thread::spawn(move || {
+                other
+                    .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+            });
+            let won = flag
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok();
+            let other_won = handle.join().unwrap();
+
+            assert!(won ^ other_won);
+        },
+        ITERATIONS,
+    );
+}
+
+/// 128-bit types are protected by a spinlock under shuttle, which has to yield while it spins.
+#[test]
+fn locked_fetch_add_does_not_lose_updates() {
+    shuttle::check_random(
+        || {
+            let counter = Arc::new(MaybeAtomicU128::new(u128::from(u64::MAX)));
+
+            let other = counter.clone();
+            let handle = thread::spawn(move || {
+                other.fetch_add(1, Ordering::AcqRel);
+            });
+            counter.fetch_add(1, Ordering::AcqRel);
+            handle.join().unwrap();
+
+            assert_eq!(counter.load(Ordering::Acquire), u128::from(u64::MAX) + 2);
+        },
+        ITERATIONS,
+    );
+}
@@ -0,0 +1,92 @@
+// MIT + Apache 2.0
+
+//! Checks that the types in `maybe_atomic::sync::atomic` behave like their counterparts in
+//! `core::sync::atomic`.
+
+#![cfg(not(any(loom, feature = "shuttle")))]
+// `compare_and_swap` is deprecated in `core` too, but still has to behave the same.
+#![allow(deprecated)]
+
+use core::sync::atomic as core_atomic;
+use maybe_atomic::sync::atomic::{self, Ordering};
+
+/// Assert that `$op` returns the same value and leaves the same value behind for the `$ty` in
+/// this crate and in `core`, both created from `$init`.
+macro_rules! same_as_core {
+    ($ty: ident, $init: expr, |$val: ident| $op: expr) => {{
+        let ours = {
+            let $val = atomic::$ty::new($init);
+            ($op, $val.into_inner())
+        };
+        let theirs = {
+            let $val = core_atomic::$ty::new($init);
+            ($op, $val.into_inner())
+        };
+        assert_eq!(ours, theirs);
+    }};
+}
+
+#[test]
+fn ptr_ops() {
+    let mut words = [0u32; 4];
+    let base = words.as_mut_ptr();
+    let end = base.wrapping_add(4);
+
+    for &ptr in &[base, end, base.wrapping_add(1)] {
+        for &val in &[0, 1, 3, usize::MAX] {
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_ptr_add(val, Ordering::AcqRel));
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_ptr_sub(val, Ordering::AcqRel));
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_byte_add(val, Ordering::AcqRel));
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_byte_sub(val, Ordering::AcqRel));
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_or(val, Ordering::AcqRel));
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_and(val, Ordering::AcqRel));
+            same_as_core!(AtomicPtr, ptr, |p| p.fetch_xor(val, Ordering::AcqRel));
+        }
+    }
+}
+
+#[test]
+fn compare_and_swap() {
+    for &order in &[
+        Ordering::Relaxed,
+        Ordering::Release,
+        Ordering::Acquire,
+        Ordering::AcqRel,
+        Ordering::SeqCst,
+    ] {
+        same_as_core!(AtomicU32, 5, |val| val.compare_and_swap(5, 6, order));
+        same_as_core!(AtomicU32, 5, |val| val.compare_and_swap(4, 6, order));
+        same_as_core!(AtomicBool, true, |val| val
+            .compare_and_swap(true, false, order));
+        same_as_core!(AtomicI64, -1, |val| val.compare_and_swap(-1, 0, order));
+    }
+}
+
+#[test]
+fn update() {
+    same_as_core!(AtomicU32, 5, |val| val.update(
+        Ordering::AcqRel,
+        Ordering::Acquire,
+        |x| x * 2
+    ));
+    same_as_core!(AtomicU64, u64::MAX, |val| val.update(
+        Ordering::AcqRel,
+        Ordering::Acquire,
+        |x| x.wrapping_add(2)
+    ));
+    same_as_core!(AtomicU32, 5, |val| val.try_update(
+        Ordering::AcqRel,
+        Ordering::Acquire,
+        |x| x.checked_sub(6)
+    ));
+    same_as_core!(AtomicU32, 5, |val| val.try_update(
+        Ordering::AcqRel,
+        Ordering::Acquire,
+        |x| x.checked_sub(2)
+    ));
+    same_as_core!(AtomicBool, false, |val| val.try_update(
+        Ordering::AcqRel,
+        Ordering::Acquire,
+        |x| Some(!x)
+    ));
+}